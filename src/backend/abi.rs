@@ -0,0 +1,187 @@
+use crate::ir::{IRFunction, IRType};
+
+/// 整数参数寄存器，按顺序使用。
+pub const ARG_REGISTERS: [&str; 8] = ["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"];
+
+/// RV64 中一个机器字（XLEN）的字节数。
+pub const XLEN_BYTES: i32 = 8;
+
+/// 值在 RV64 上占用的字节数。
+pub fn type_size(ty: &IRType) -> i32 {
+    match ty {
+        IRType::Bool => 1,
+        IRType::Address | IRType::String => 8,
+        // 放进能容纳该位宽的最小的整数类型中，超过 64 位的按 256 位处理
+        IRType::Uint(bits) | IRType::Int(bits) => match bits {
+            0..=8 => 1,
+            9..=16 => 2,
+            17..=32 => 4,
+            33..=64 => 8,
+            _ => 32,
+        },
+    }
+}
+
+/// 放不进一个寄存器的值（256 位整数）。这类值总是保存在内存中，
+/// 按引用传参，并通过隐藏指针返回。
+pub fn is_wide(ty: &IRType) -> bool {
+    type_size(ty) > XLEN_BYTES
+}
+
+/// 值占用的机器字数。
+fn words(ty: &IRType) -> i32 {
+    (type_size(ty) + XLEN_BYTES - 1) / XLEN_BYTES
+}
+
+/// 参数在调用约定中的位置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgLocation {
+    Register(&'static str),
+    /// 调用者栈帧出参区中的槽位，偏移相对于调用发生时的 sp（即被调用者的 fp）
+    Stack(i32),
+}
+
+/// 返回值在调用约定中的位置。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReturnLocation {
+    /// 依次放在 a0、a1 中
+    Registers(Vec<&'static str>),
+    /// 写入调用者提供的缓冲区，缓冲区地址作为隐藏的第一个参数在 a0 中传入；
+    /// 记录每个返回值在缓冲区中的偏移
+    Memory(Vec<i32>),
+}
+
+/// 按 RISC-V LP64 psABI 为一个函数签名确定参数和返回值的位置。
+///
+/// 多个返回值按结构体处理，每个值占整数个机器字：总共不超过两个字时通过 a0/a1 返回，
+/// 否则通过隐藏指针返回。前八个参数（有隐藏指针时为七个）使用 a0-a7，其余参数按顺序
+/// 放在栈上，每个占一个 8 字节槽位。超过两个字的参数（256 位整数）按引用传递，
+/// 寄存器或栈槽中放的是指向其值的指针。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionAbi {
+    pub params: Vec<ArgLocation>,
+    pub returns: ReturnLocation,
+    /// 栈上传递的参数占用的字节数，按 16 字节对齐
+    pub stack_args_size: i32,
+}
+
+impl FunctionAbi {
+    pub fn new(params: &[IRType], returns: &[IRType]) -> Self {
+        let return_words: i32 = returns.iter().map(words).sum();
+        let indirect = return_words > 2;
+
+        let mut next_register = if indirect { 1 } else { 0 };
+        let mut stack_offset = 0;
+        let params = params
+            .iter()
+            .map(|_| match ARG_REGISTERS.get(next_register) {
+                Some(reg) => {
+                    next_register += 1;
+                    ArgLocation::Register(reg)
+                }
+                None => {
+                    let location = ArgLocation::Stack(stack_offset);
+                    stack_offset += XLEN_BYTES;
+                    location
+                }
+            })
+            .collect();
+
+        let returns = if indirect {
+            let mut offset = 0;
+            ReturnLocation::Memory(
+                returns
+                    .iter()
+                    .map(|ty| {
+                        let location = offset;
+                        offset += words(ty) * XLEN_BYTES;
+                        location
+                    })
+                    .collect(),
+            )
+        } else {
+            ReturnLocation::Registers(ARG_REGISTERS[..returns.len()].to_vec())
+        };
+
+        Self {
+            params,
+            returns,
+            stack_args_size: (stack_offset + 15) / 16 * 16,
+        }
+    }
+
+    pub fn for_function(func: &IRFunction) -> Self {
+        let params: Vec<_> = func.params.iter().map(|(_, ty)| ty.clone()).collect();
+        Self::new(&params, &func.returns)
+    }
+
+    /// 是否通过 a0 中的隐藏指针返回。
+    pub fn returns_indirectly(&self) -> bool {
+        matches!(self.returns, ReturnLocation::Memory(_))
+    }
+
+    /// 入口处被参数（包括隐藏的返回指针）占用的寄存器。
+    pub fn incoming_registers(&self) -> Vec<&'static str> {
+        let mut registers: Vec<_> = self
+            .params
+            .iter()
+            .filter_map(|location| match location {
+                ArgLocation::Register(reg) => Some(*reg),
+                ArgLocation::Stack(_) => None,
+            })
+            .collect();
+        if self.returns_indirectly() {
+            registers.insert(0, ARG_REGISTERS[0]);
+        }
+        registers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_params_and_return() {
+        let abi = FunctionAbi::new(&[IRType::Int(64), IRType::Bool], &[IRType::Int(64)]);
+        assert_eq!(
+            abi.params,
+            vec![ArgLocation::Register("a0"), ArgLocation::Register("a1")]
+        );
+        assert_eq!(abi.returns, ReturnLocation::Registers(vec!["a0"]));
+        assert_eq!(abi.stack_args_size, 0);
+    }
+
+    #[test]
+    fn test_extra_params_on_stack() {
+        let params = vec![IRType::Int(64); 11];
+        let abi = FunctionAbi::new(&params, &[IRType::Int(64), IRType::Int(64)]);
+        assert_eq!(abi.params[7], ArgLocation::Register("a7"));
+        assert_eq!(abi.params[8], ArgLocation::Stack(0));
+        assert_eq!(abi.params[10], ArgLocation::Stack(16));
+        assert_eq!(abi.stack_args_size, 32);
+        assert_eq!(abi.returns, ReturnLocation::Registers(vec!["a0", "a1"]));
+    }
+
+    #[test]
+    fn test_large_return_uses_hidden_pointer() {
+        let abi = FunctionAbi::new(&[IRType::Int(64)], &[IRType::Int(64), IRType::Bool, IRType::Int(64)]);
+        assert!(abi.returns_indirectly());
+        assert_eq!(abi.params, vec![ArgLocation::Register("a1")]);
+        assert_eq!(abi.returns, ReturnLocation::Memory(vec![0, 8, 16]));
+        assert_eq!(abi.incoming_registers(), vec!["a0", "a1"]);
+    }
+
+    #[test]
+    fn test_wide_values() {
+        let abi = FunctionAbi::new(&[IRType::Uint(256), IRType::Int(64)], &[IRType::Bool, IRType::Int(256)]);
+        assert!(is_wide(&IRType::Uint(256)));
+        assert!(!is_wide(&IRType::Int(64)));
+        // 256 位参数按引用传递，只占一个寄存器
+        assert_eq!(
+            abi.params,
+            vec![ArgLocation::Register("a1"), ArgLocation::Register("a2")]
+        );
+        assert_eq!(abi.returns, ReturnLocation::Memory(vec![0, 8]));
+    }
+}
@@ -0,0 +1,28 @@
+//! 合约运行环境（RISC-V 智能合约虚拟机）提供的主机函数。
+//!
+//! 主机函数通过 `ecall` 调用：编号放在 a7 中，参数依次放在 a0-a2 中。与普通函数调用
+//! 一样，返回后调用者保存的寄存器（t0-t6、a0-a7）都可能被改写，只有被调用者保存的
+//! 寄存器保持不变。存储的键和值都是 32 字节的 256 位整数，按小端序在内存中传递，
+//! 与 256 位值在栈帧中的表示相同。
+
+use crate::ir::Instruction;
+
+/// 从存储读出一个字（a0 = 键的地址，a1 = 结果的写入地址），未写入过的槽读出 0。
+pub const STORAGE_LOAD: u32 = 0x100;
+/// 向存储写入一个字（a0 = 键的地址，a1 = 值的地址）。
+pub const STORAGE_STORE: u32 = 0x101;
+/// 计算 Keccak-256 摘要（a0 = 数据地址，a1 = 字节数，a2 = 32 字节摘要的写入地址）。
+pub const KECCAK256: u32 = 0x102;
+
+/// 指令是否会调用主机函数。与函数调用一样，这类指令会破坏调用者保存的寄存器。
+pub fn calls_host(inst: &Instruction) -> bool {
+    matches!(
+        inst,
+        Instruction::StorageLoad(..) | Instruction::StorageStore(..) | Instruction::MappingSlot(..)
+    )
+}
+
+/// 调用编号为 `function` 的主机函数，参数须已放入 a0-a2。
+pub fn call(function: u32) -> String {
+    format!("    li a7, {function}\n    ecall\n")
+}
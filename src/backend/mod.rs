@@ -1 +1,5 @@
-pub mod riscv; 
\ No newline at end of file
+pub mod abi;
+pub mod host;
+pub mod regalloc;
+pub mod riscv;
+pub mod runtime;
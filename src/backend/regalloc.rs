@@ -0,0 +1,419 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::backend::abi::{ArgLocation, FunctionAbi, ARG_REGISTERS};
+use crate::backend::host::calls_host;
+use crate::backend::runtime::{calls_runtime, is_wide_value};
+use crate::ir::cfg::Cfg;
+use crate::ir::{IRFunction, Instruction, Value};
+
+/// 可分配的调用者保存临时寄存器（t5/t6 留作代码生成时的暂存寄存器）。
+pub const TEMP_REGISTERS: [&str; 5] = ["t0", "t1", "t2", "t3", "t4"];
+/// 被调用者保存的寄存器（s0 用作帧指针）。
+pub const CALLEE_SAVED_REGISTERS: [&str; 11] =
+    ["s1", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11"];
+/// 代码生成时用于加载常量和溢出值的暂存寄存器，不参与分配。
+pub const SCRATCH_REGISTERS: [&str; 2] = ["t5", "t6"];
+
+/// 值在函数执行期间所处的位置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    Register(&'static str),
+    /// 溢出到栈上的第 n 个槽位
+    Stack(usize),
+}
+
+/// 一个值的活跃区间，以线性化后的指令编号表示（闭区间）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveInterval {
+    pub value: Value,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 寄存器分配的结果。
+#[derive(Debug, Clone, Default)]
+pub struct Allocation {
+    pub locations: HashMap<Value, Location>,
+    pub spill_slots: usize,
+    /// 用到的被调用者保存寄存器，需要在序言中保存、在结语中恢复
+    pub callee_saved: Vec<&'static str>,
+}
+
+impl Allocation {
+    pub fn location(&self, value: &Value) -> Option<Location> {
+        self.locations.get(value).copied()
+    }
+}
+
+/// 对没有 phi 的函数做线性扫描寄存器分配（Poletto & Sarkar）。
+///
+/// 跨越函数调用的区间只会分配到被调用者保存的寄存器，否则溢出到栈上；
+/// 参数优先留在传入它的 a 寄存器中。
+pub fn allocate(func: &IRFunction) -> Allocation {
+    let intervals = live_intervals(func);
+    let calls = call_positions(func);
+    let abi = FunctionAbi::for_function(func);
+    let incoming = abi.incoming_registers();
+
+    let mut allocation = Allocation::default();
+    // 活跃列表按区间终点排序
+    let mut active: Vec<(LiveInterval, &'static str)> = Vec::new();
+    let mut free: Vec<&'static str> = TEMP_REGISTERS
+        .iter()
+        .chain(ARG_REGISTERS.iter())
+        .chain(CALLEE_SAVED_REGISTERS.iter())
+        .copied()
+        .collect();
+
+    for interval in intervals {
+        // 释放已经结束的区间
+        active.retain(|(other, reg)| {
+            if other.end < interval.start {
+                free.push(reg);
+                false
+            } else {
+                true
+            }
+        });
+
+        let crosses_call = calls.iter().any(|&c| interval.start < c && c < interval.end);
+        let preferred = param_register(func, &abi, &interval.value);
+        let choice = free
+            .iter()
+            .position(|reg| Some(*reg) == preferred && !crosses_call)
+            .or_else(|| {
+                free.iter().position(|reg| {
+                    (!crosses_call || CALLEE_SAVED_REGISTERS.contains(reg))
+                        // 函数入口处，尚未取用的参数仍占据各自的寄存器
+                        && !(interval.start == 0 && incoming.contains(reg))
+                })
+            });
+
+        match choice {
+            Some(i) => {
+                let reg = free.remove(i);
+                if CALLEE_SAVED_REGISTERS.contains(&reg) && !allocation.callee_saved.contains(&reg) {
+                    allocation.callee_saved.push(reg);
+                }
+                allocation
+                    .locations
+                    .insert(interval.value.clone(), Location::Register(reg));
+                let at = active.partition_point(|(other, _)| other.end <= interval.end);
+                active.insert(at, (interval, reg));
+            }
+            None => spill_at_interval(&mut allocation, &mut active, interval, crosses_call),
+        }
+    }
+
+    allocation.callee_saved.sort_by_key(|reg| {
+        CALLEE_SAVED_REGISTERS.iter().position(|r| r == reg)
+    });
+    allocation
+}
+
+/// 没有空闲寄存器时，溢出活跃列表中终点最远且寄存器可用的区间。
+fn spill_at_interval(
+    allocation: &mut Allocation,
+    active: &mut Vec<(LiveInterval, &'static str)>,
+    interval: LiveInterval,
+    crosses_call: bool,
+) {
+    let victim = active.iter().rposition(|(other, reg)| {
+        other.end > interval.end && (!crosses_call || CALLEE_SAVED_REGISTERS.contains(reg))
+    });
+
+    let spilled = match victim {
+        Some(i) => {
+            let (victim, reg) = active.remove(i);
+            allocation
+                .locations
+                .insert(interval.value.clone(), Location::Register(reg));
+            let at = active.partition_point(|(other, _)| other.end <= interval.end);
+            active.insert(at, (interval, reg));
+            victim.value
+        }
+        None => interval.value,
+    };
+
+    allocation
+        .locations
+        .insert(spilled, Location::Stack(allocation.spill_slots));
+    allocation.spill_slots += 1;
+}
+
+fn param_register(func: &IRFunction, abi: &FunctionAbi, value: &Value) -> Option<&'static str> {
+    let Value::Local(name) = value else {
+        return None;
+    };
+    let index = func.params.iter().position(|(param, _)| param == name)?;
+    match abi.params.get(index) {
+        Some(ArgLocation::Register(reg)) => Some(reg),
+        _ => None,
+    }
+}
+
+/// 按基本块顺序线性化后，每条 `Call` 指令以及调用运行时例程或主机函数的指令的编号。
+fn call_positions(func: &IRFunction) -> Vec<usize> {
+    let mut positions = Vec::new();
+    let mut pos = 1;
+    for block in &func.blocks {
+        for inst in &block.instructions {
+            if matches!(inst, Instruction::Call(..)) || calls_runtime(func, inst) || calls_host(inst) {
+                positions.push(pos);
+            }
+            pos += 1;
+        }
+        pos += 1; // 终结指令
+    }
+    positions
+}
+
+/// 临时值和参数参与分配；仍留在 `IRFunction::locals` 中的局部变量和 256 位的值
+/// 在栈帧里有固定槽位。
+fn is_allocatable(func: &IRFunction, value: &Value) -> bool {
+    let candidate = match value {
+        Value::Temporary(_) => true,
+        Value::Local(name) => !func.locals.contains_key(name),
+        Value::Constant(_) => false,
+    };
+    candidate && !is_wide_value(func, value)
+}
+
+/// 计算所有参与分配的值的活跃区间，按起点排序。
+///
+/// 指令按基本块顺序从 1 开始编号，每个块的终结指令也占一个编号；
+/// 参数在编号 0（函数入口）处定义。
+pub fn live_intervals(func: &IRFunction) -> Vec<LiveInterval> {
+    let cfg = Cfg::new(func);
+    let count = func.blocks.len();
+
+    // 每个块的局部 use/def 集合
+    let mut uses: Vec<HashSet<Value>> = vec![HashSet::new(); count];
+    let mut defs: Vec<HashSet<Value>> = vec![HashSet::new(); count];
+    for (i, block) in func.blocks.iter().enumerate() {
+        for inst in &block.instructions {
+            for operand in inst.operands() {
+                if is_allocatable(func, operand) && !defs[i].contains(operand) {
+                    uses[i].insert(operand.clone());
+                }
+            }
+            if let Some(dest) = inst.dest().filter(|dest| is_allocatable(func, dest)) {
+                defs[i].insert(dest.clone());
+            }
+        }
+        for operand in block.terminator.operands() {
+            if is_allocatable(func, operand) && !defs[i].contains(operand) {
+                uses[i].insert(operand.clone());
+            }
+        }
+    }
+
+    // 逆向迭代求解 live-in / live-out
+    let mut live_in: Vec<HashSet<Value>> = vec![HashSet::new(); count];
+    let mut live_out: Vec<HashSet<Value>> = vec![HashSet::new(); count];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in (0..count).rev() {
+            let mut out = HashSet::new();
+            for &succ in &cfg.succs[i] {
+                out.extend(live_in[succ].iter().cloned());
+            }
+            let mut inn: HashSet<Value> = out.difference(&defs[i]).cloned().collect();
+            inn.extend(uses[i].iter().cloned());
+            if inn != live_in[i] || out != live_out[i] {
+                live_in[i] = inn;
+                live_out[i] = out;
+                changed = true;
+            }
+        }
+    }
+
+    let mut ranges: HashMap<Value, (usize, usize)> = HashMap::new();
+    let mut extend = |value: &Value, pos: usize| {
+        ranges
+            .entry(value.clone())
+            .and_modify(|(start, end)| {
+                *start = (*start).min(pos);
+                *end = (*end).max(pos);
+            })
+            .or_insert((pos, pos));
+    };
+
+    for (name, _) in &func.params {
+        extend(&Value::Local(name.clone()), 0);
+    }
+
+    let mut pos = 1;
+    for (i, block) in func.blocks.iter().enumerate() {
+        let block_start = pos;
+        for value in &live_in[i] {
+            extend(value, block_start);
+        }
+        for inst in &block.instructions {
+            for operand in inst.operands() {
+                if is_allocatable(func, operand) {
+                    extend(operand, pos);
+                }
+            }
+            if let Some(dest) = inst.dest().filter(|dest| is_allocatable(func, dest)) {
+                extend(dest, pos);
+            }
+            // 写入与其他变量共用的槽时先调用主机函数读出整个槽，写入的值要在调用之后仍然可用
+            if let Instruction::StorageStore(_, _, value) = inst {
+                if is_allocatable(func, value) {
+                    extend(value, pos + 1);
+                }
+            }
+            pos += 1;
+        }
+        for operand in block.terminator.operands() {
+            if is_allocatable(func, operand) {
+                extend(operand, pos);
+            }
+        }
+        for value in &live_out[i] {
+            extend(value, pos);
+        }
+        pos += 1;
+    }
+
+    let mut intervals: Vec<_> = ranges
+        .into_iter()
+        .map(|(value, (start, end))| LiveInterval { value, start, end })
+        .collect();
+    // 起点相同时按参数顺序、再按值排序，保证输出稳定
+    intervals.sort_by_key(|interval| {
+        let param_index = match &interval.value {
+            Value::Local(name) => func.params.iter().position(|(p, _)| p == name),
+            _ => None,
+        };
+        let order = match &interval.value {
+            Value::Temporary(id) => (*id as usize, String::new()),
+            Value::Local(name) => (0, name.clone()),
+            Value::Constant(_) => (0, String::new()),
+        };
+        (interval.start, param_index.unwrap_or(usize::MAX), order)
+    });
+    intervals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BasicBlock, IRType, Terminator};
+
+    fn local(name: &str) -> Value {
+        Value::Local(name.to_string())
+    }
+
+    fn straight_line(func: &mut IRFunction, instructions: Vec<Instruction>, ret: Value) {
+        func.add_block(BasicBlock {
+            label: "entry".to_string(),
+            instructions,
+            terminator: Terminator::Return(vec![ret]),
+        });
+    }
+
+    #[test]
+    fn test_params_stay_in_arg_registers() {
+        let mut func = IRFunction::new("add".to_string());
+        func.params.push(("a".to_string(), IRType::Int(64)));
+        func.params.push(("b".to_string(), IRType::Int(64)));
+        let sum = func.new_temp(IRType::Int(64));
+        straight_line(
+            &mut func,
+            vec![Instruction::Add(sum.clone(), local("a"), local("b"))],
+            sum.clone(),
+        );
+
+        let allocation = allocate(&func);
+        assert_eq!(allocation.location(&local("a")), Some(Location::Register("a0")));
+        assert_eq!(allocation.location(&local("b")), Some(Location::Register("a1")));
+        assert_eq!(allocation.location(&sum), Some(Location::Register("t0")));
+        assert!(allocation.callee_saved.is_empty());
+        assert_eq!(allocation.spill_slots, 0);
+    }
+
+    #[test]
+    fn test_values_live_across_call_use_callee_saved() {
+        let mut func = IRFunction::new("f".to_string());
+        func.params.push(("a".to_string(), IRType::Int(64)));
+        let result = func.new_temp(IRType::Int(64));
+        let sum = func.new_temp(IRType::Int(64));
+        straight_line(
+            &mut func,
+            vec![
+                Instruction::Call(Some(result.clone()), "g".to_string(), Vec::new()),
+                Instruction::Add(sum.clone(), local("a"), result.clone()),
+            ],
+            sum,
+        );
+
+        let allocation = allocate(&func);
+        assert_eq!(allocation.location(&local("a")), Some(Location::Register("s1")));
+        assert_eq!(allocation.callee_saved, vec!["s1"]);
+    }
+
+    #[test]
+    fn test_spills_when_out_of_registers() {
+        let mut func = IRFunction::new("f".to_string());
+        let one = Value::int(1);
+        let count = TEMP_REGISTERS.len() + ARG_REGISTERS.len() + CALLEE_SAVED_REGISTERS.len() + 2;
+        let temps: Vec<_> = (0..count).map(|_| func.new_temp(IRType::Int(64))).collect();
+
+        // 先定义所有临时值，再依次累加，使它们同时活跃
+        let mut instructions: Vec<_> = temps
+            .iter()
+            .map(|t| Instruction::Move(t.clone(), one.clone()))
+            .collect();
+        let mut acc = temps[0].clone();
+        for t in &temps[1..] {
+            let sum = func.new_temp(IRType::Int(64));
+            instructions.push(Instruction::Add(sum.clone(), acc, t.clone()));
+            acc = sum;
+        }
+        straight_line(&mut func, instructions, acc);
+
+        let allocation = allocate(&func);
+        assert!(allocation.spill_slots > 0);
+        let registers: Vec<_> = temps
+            .iter()
+            .filter_map(|t| match allocation.location(t) {
+                Some(Location::Register(reg)) => Some(reg),
+                _ => None,
+            })
+            .collect();
+        let unique: HashSet<_> = registers.iter().collect();
+        assert_eq!(unique.len(), registers.len());
+    }
+
+    #[test]
+    fn test_live_intervals_across_loop() {
+        // entry: x = 0 -> loop: x2 = x + 1; branch loop/exit -> exit: return x
+        let mut func = IRFunction::new("f".to_string());
+        let x = func.new_temp(IRType::Int(64));
+        let y = func.new_temp(IRType::Int(64));
+        func.add_block(BasicBlock {
+            label: "entry".to_string(),
+            instructions: vec![Instruction::Move(x.clone(), Value::int(0))],
+            terminator: Terminator::Jump("loop".to_string()),
+        });
+        func.add_block(BasicBlock {
+            label: "loop".to_string(),
+            instructions: vec![Instruction::Add(y.clone(), x.clone(), x.clone())],
+            terminator: Terminator::Branch(y.clone(), "loop".to_string(), "exit".to_string()),
+        });
+        func.add_block(BasicBlock {
+            label: "exit".to_string(),
+            instructions: Vec::new(),
+            terminator: Terminator::Return(vec![x.clone()]),
+        });
+
+        let intervals = live_intervals(&func);
+        let x_interval = intervals.iter().find(|i| i.value == x).unwrap();
+        assert_eq!((x_interval.start, x_interval.end), (1, 5));
+        let y_interval = intervals.iter().find(|i| i.value == y).unwrap();
+        assert_eq!((y_interval.start, y_interval.end), (3, 4));
+    }
+}
@@ -0,0 +1,531 @@
+use crate::backend::abi::is_wide;
+use crate::ir::{IRFunction, Instruction, Value};
+
+/// 256 位整数的字数。值在内存中按小端序存放，最低的字在最低地址。
+const LIMBS: i32 = 4;
+
+/// 256 位整数运算例程的符号名。所有例程都通过指针访问操作数和结果，
+/// 结果指针可以与操作数指针相同。
+pub const U256_ADD: &str = "__u256_add";
+pub const U256_SUB: &str = "__u256_sub";
+pub const U256_MUL: &str = "__u256_mul";
+pub const U256_DIV: &str = "__u256_div";
+pub const U256_MOD: &str = "__u256_mod";
+pub const I256_DIV: &str = "__i256_div";
+pub const I256_MOD: &str = "__i256_mod";
+pub const U256_CMP: &str = "__u256_cmp";
+pub const I256_CMP: &str = "__i256_cmp";
+pub const U256_AND: &str = "__u256_and";
+pub const U256_OR: &str = "__u256_or";
+pub const U256_XOR: &str = "__u256_xor";
+pub const U256_SHL: &str = "__u256_shl";
+pub const U256_SHR: &str = "__u256_shr";
+pub const I256_SAR: &str = "__i256_sar";
+pub const U256_BSWAP: &str = "__u256_bswap";
+
+const U256_DIVMOD: &str = "__u256_divmod";
+const I256_NEG: &str = "__i256_neg";
+const I256_ABS: &str = "__i256_abs";
+
+/// 指令是否需要调用运行时例程，也就是会破坏调用者保存的寄存器。
+pub fn calls_runtime(func: &IRFunction, inst: &Instruction) -> bool {
+    match inst {
+        Instruction::Add(_, lhs, rhs)
+        | Instruction::Sub(_, lhs, rhs)
+        | Instruction::Mul(_, lhs, rhs)
+        | Instruction::Div(_, lhs, rhs)
+        | Instruction::Mod(_, lhs, rhs)
+        | Instruction::And(_, lhs, rhs)
+        | Instruction::Or(_, lhs, rhs)
+        | Instruction::Xor(_, lhs, rhs)
+        | Instruction::Eq(_, lhs, rhs)
+        | Instruction::Ne(_, lhs, rhs)
+        | Instruction::Lt(_, lhs, rhs)
+        | Instruction::Gt(_, lhs, rhs)
+        | Instruction::Le(_, lhs, rhs)
+        | Instruction::Ge(_, lhs, rhs) => is_wide_value(func, lhs) || is_wide_value(func, rhs),
+        // 移位两侧的类型互不相关，字面量左移得到的也可能是 256 位的结果
+        Instruction::Shl(dest, lhs, rhs) | Instruction::Shr(dest, lhs, rhs) => {
+            [dest, lhs, rhs].into_iter().any(|value| is_wide_value(func, value))
+        }
+        _ => false,
+    }
+}
+
+pub fn is_wide_value(func: &IRFunction, value: &Value) -> bool {
+    func.value_type(value).is_some_and(|ty| is_wide(&ty))
+}
+
+/// 运行时例程的汇编代码，用到 256 位运算时附加在输出末尾。
+///
+/// 例程只使用调用者保存的寄存器（除法会保存并恢复它用到的 s 寄存器），
+/// 语义与 EVM 一致：加减乘按 2^256 取模，除数为 0 时商和余数都为 0，
+/// 有符号除法向零取整，有符号取模的结果与被除数同号，移位数不小于 256 时
+/// 结果为 0（算术右移为符号位填充）。
+pub fn source() -> String {
+    [
+        add(),
+        sub(),
+        mul(),
+        divmod(),
+        unsigned_div(),
+        unsigned_mod(),
+        neg(),
+        abs(),
+        signed_div(I256_DIV, false),
+        signed_div(I256_MOD, true),
+        compare(U256_CMP, false),
+        compare(I256_CMP, true),
+        bitwise(U256_AND, "and"),
+        bitwise(U256_OR, "or"),
+        bitwise(U256_XOR, "xor"),
+        shift(U256_SHL, Shift::Left),
+        shift(U256_SHR, Shift::Right),
+        shift(I256_SAR, Shift::Arithmetic),
+        bswap(),
+    ]
+    .concat()
+}
+
+fn header(name: &str) -> String {
+    format!(".globl {name}\n{name}:\n")
+}
+
+/// dest = lhs + rhs（a0 = dest，a1 = lhs，a2 = rhs）
+fn add() -> String {
+    let mut asm = header(U256_ADD);
+    asm.push_str("    li t0, 0\n"); // 进位
+    for i in 0..LIMBS {
+        let offset = i * 8;
+        asm.push_str(&format!("    ld t1, {offset}(a1)\n"));
+        asm.push_str(&format!("    ld t2, {offset}(a2)\n"));
+        asm.push_str("    add t1, t1, t0\n");
+        asm.push_str("    sltu t0, t1, t0\n");
+        asm.push_str("    add t1, t1, t2\n");
+        asm.push_str("    sltu t3, t1, t2\n");
+        asm.push_str("    or t0, t0, t3\n");
+        asm.push_str(&format!("    sd t1, {offset}(a0)\n"));
+    }
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// dest = lhs - rhs（a0 = dest，a1 = lhs，a2 = rhs）
+fn sub() -> String {
+    let mut asm = header(U256_SUB);
+    asm.push_str("    li t0, 0\n"); // 借位
+    for i in 0..LIMBS {
+        let offset = i * 8;
+        asm.push_str(&format!("    ld t1, {offset}(a1)\n"));
+        asm.push_str(&format!("    ld t2, {offset}(a2)\n"));
+        asm.push_str("    sltu t3, t1, t2\n");
+        asm.push_str("    sub t1, t1, t2\n");
+        asm.push_str("    sltu t4, t1, t0\n");
+        asm.push_str("    sub t1, t1, t0\n");
+        asm.push_str("    or t0, t3, t4\n");
+        asm.push_str(&format!("    sd t1, {offset}(a0)\n"));
+    }
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// dest = lhs * rhs（a0 = dest，a1 = lhs，a2 = rhs），逐字相乘，只保留低 256 位。
+fn mul() -> String {
+    const PRODUCT: [&str; 4] = ["a4", "a5", "a6", "a7"];
+
+    let mut asm = header(U256_MUL);
+    for reg in PRODUCT {
+        asm.push_str(&format!("    li {reg}, 0\n"));
+    }
+    for i in 0..LIMBS {
+        asm.push_str(&format!("    ld t0, {}(a1)\n", i * 8));
+        asm.push_str("    li t4, 0\n"); // 向高位的进位
+        for j in 0..LIMBS - i {
+            // product[i + j] += lhs[i] * rhs[j] + 进位，新的进位不会超过 64 位
+            let acc = PRODUCT[(i + j) as usize];
+            asm.push_str(&format!("    ld t1, {}(a2)\n", j * 8));
+            asm.push_str("    mul t2, t0, t1\n");
+            asm.push_str("    mulhu t3, t0, t1\n");
+            asm.push_str(&format!("    add {acc}, {acc}, t2\n"));
+            asm.push_str(&format!("    sltu t5, {acc}, t2\n"));
+            asm.push_str("    add t3, t3, t5\n");
+            asm.push_str(&format!("    add {acc}, {acc}, t4\n"));
+            asm.push_str(&format!("    sltu t5, {acc}, t4\n"));
+            asm.push_str("    add t4, t3, t5\n");
+        }
+    }
+    for (i, reg) in PRODUCT.iter().enumerate() {
+        asm.push_str(&format!("    sd {reg}, {}(a0)\n", i * 8));
+    }
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// 无符号除法（a0 = 商或 0，a1 = 余数或 0，a2 = 被除数，a3 = 除数），逐位移位相减。
+fn divmod() -> String {
+    const QUOTIENT: [&str; 4] = ["a4", "a5", "a6", "a7"];
+    const REMAINDER: [&str; 4] = ["t0", "t1", "t2", "t3"];
+    const DIVISOR: [&str; 4] = ["s1", "s2", "s3", "s4"];
+    let label = |name: &str| format!(".L{U256_DIVMOD}_{name}");
+
+    let mut asm = header(U256_DIVMOD);
+    asm.push_str("    addi sp, sp, -32\n");
+    for (i, reg) in DIVISOR.iter().enumerate() {
+        asm.push_str(&format!("    sd {reg}, {}(sp)\n", i * 8));
+    }
+    for i in 0..LIMBS as usize {
+        asm.push_str(&format!("    ld {}, {}(a3)\n", DIVISOR[i], i * 8));
+        asm.push_str(&format!("    ld {}, {}(a2)\n", QUOTIENT[i], i * 8));
+        asm.push_str(&format!("    li {}, 0\n", REMAINDER[i]));
+    }
+
+    // 除数为 0 时商和余数都为 0
+    asm.push_str(&format!("    or t4, {}, {}\n", DIVISOR[0], DIVISOR[1]));
+    asm.push_str(&format!("    or t4, t4, {}\n", DIVISOR[2]));
+    asm.push_str(&format!("    or t4, t4, {}\n", DIVISOR[3]));
+    asm.push_str(&format!("    bnez t4, {}\n", label("start")));
+    for reg in QUOTIENT {
+        asm.push_str(&format!("    li {reg}, 0\n"));
+    }
+    asm.push_str(&format!("    j {}\n", label("store")));
+    asm.push_str(&format!("{}:\n", label("start")));
+    asm.push_str("    li a2, 256\n");
+
+    // 把 (余数, 商) 作为一个 512 位的数左移一位，移出余数的最高位记在 t6 中
+    asm.push_str(&format!("{}:\n", label("loop")));
+    asm.push_str(&format!("    srli t6, {}, 63\n", REMAINDER[3]));
+    let limbs: Vec<_> = QUOTIENT.iter().chain(REMAINDER.iter()).collect();
+    for i in (1..limbs.len()).rev() {
+        asm.push_str(&format!("    srli t4, {}, 63\n", limbs[i - 1]));
+        asm.push_str(&format!("    slli {0}, {0}, 1\n", limbs[i]));
+        asm.push_str(&format!("    or {0}, {0}, t4\n", limbs[i]));
+    }
+    asm.push_str(&format!("    slli {0}, {0}, 1\n", QUOTIENT[0]));
+
+    // 余数不小于除数（或者左移时溢出）时减去除数，并把商的最低位置 1
+    asm.push_str(&format!("    bnez t6, {}\n", label("subtract")));
+    for i in (0..LIMBS as usize).rev() {
+        asm.push_str(&format!("    bltu {}, {}, {}\n", REMAINDER[i], DIVISOR[i], label("next")));
+        if i > 0 {
+            asm.push_str(&format!("    bltu {}, {}, {}\n", DIVISOR[i], REMAINDER[i], label("subtract")));
+        }
+    }
+    asm.push_str(&format!("{}:\n", label("subtract")));
+    asm.push_str(&format!("    sltu t4, {}, {}\n", REMAINDER[0], DIVISOR[0]));
+    asm.push_str(&format!("    sub {0}, {0}, {1}\n", REMAINDER[0], DIVISOR[0]));
+    for i in 1..LIMBS as usize {
+        let (r, d) = (REMAINDER[i], DIVISOR[i]);
+        asm.push_str(&format!("    sltu t5, {r}, {d}\n"));
+        asm.push_str(&format!("    sub {r}, {r}, {d}\n"));
+        asm.push_str(&format!("    sltu t6, {r}, t4\n"));
+        asm.push_str(&format!("    sub {r}, {r}, t4\n"));
+        asm.push_str("    or t4, t5, t6\n");
+    }
+    asm.push_str(&format!("    ori {0}, {0}, 1\n", QUOTIENT[0]));
+    asm.push_str(&format!("{}:\n", label("next")));
+    asm.push_str("    addi a2, a2, -1\n");
+    asm.push_str(&format!("    bnez a2, {}\n", label("loop")));
+
+    // 写回调用者需要的结果
+    asm.push_str(&format!("{}:\n", label("store")));
+    asm.push_str(&format!("    beqz a0, {}\n", label("remainder")));
+    for (i, reg) in QUOTIENT.iter().enumerate() {
+        asm.push_str(&format!("    sd {reg}, {}(a0)\n", i * 8));
+    }
+    asm.push_str(&format!("{}:\n", label("remainder")));
+    asm.push_str(&format!("    beqz a1, {}\n", label("done")));
+    for (i, reg) in REMAINDER.iter().enumerate() {
+        asm.push_str(&format!("    sd {reg}, {}(a1)\n", i * 8));
+    }
+    asm.push_str(&format!("{}:\n", label("done")));
+    for (i, reg) in DIVISOR.iter().enumerate() {
+        asm.push_str(&format!("    ld {reg}, {}(sp)\n", i * 8));
+    }
+    asm.push_str("    addi sp, sp, 32\n");
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// dest = lhs / rhs（无符号）
+fn unsigned_div() -> String {
+    let mut asm = header(U256_DIV);
+    asm.push_str("    mv a3, a2\n");
+    asm.push_str("    mv a2, a1\n");
+    asm.push_str("    li a1, 0\n");
+    asm.push_str(&format!("    tail {U256_DIVMOD}\n"));
+    asm
+}
+
+/// dest = lhs % rhs（无符号）
+fn unsigned_mod() -> String {
+    let mut asm = header(U256_MOD);
+    asm.push_str("    mv a3, a2\n");
+    asm.push_str("    mv a2, a1\n");
+    asm.push_str("    mv a1, a0\n");
+    asm.push_str("    li a0, 0\n");
+    asm.push_str(&format!("    tail {U256_DIVMOD}\n"));
+    asm
+}
+
+/// dest = -src（a0 = dest，a1 = src），即按位取反再加一。
+fn neg() -> String {
+    let mut asm = header(I256_NEG);
+    asm.push_str("    li t0, 1\n");
+    for i in 0..LIMBS {
+        let offset = i * 8;
+        asm.push_str(&format!("    ld t1, {offset}(a1)\n"));
+        asm.push_str("    not t1, t1\n");
+        asm.push_str("    add t1, t1, t0\n");
+        asm.push_str("    sltu t0, t1, t0\n");
+        asm.push_str(&format!("    sd t1, {offset}(a0)\n"));
+    }
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// dest = |src|（a0 = dest，a1 = src）。-2^255 的绝对值按无符号数解释为 2^255。
+fn abs() -> String {
+    let copy = format!(".L{I256_ABS}_copy");
+    let mut asm = header(I256_ABS);
+    asm.push_str(&format!("    ld t0, {}(a1)\n", (LIMBS - 1) * 8));
+    asm.push_str(&format!("    bgez t0, {copy}\n"));
+    asm.push_str(&format!("    tail {I256_NEG}\n"));
+    asm.push_str(&format!("{copy}:\n"));
+    for i in 0..LIMBS {
+        asm.push_str(&format!("    ld t0, {}(a1)\n", i * 8));
+        asm.push_str(&format!("    sd t0, {}(a0)\n", i * 8));
+    }
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// 有符号除法或取模（a0 = dest，a1 = lhs，a2 = rhs）：对绝对值做无符号除法，再修正符号。
+fn signed_div(name: &str, remainder: bool) -> String {
+    let done = format!(".L{name}_done");
+    let mut asm = header(name);
+    // 栈上依次是 |lhs|、|rhs|、结果是否为负、rhs、dest 和 ra
+    asm.push_str("    addi sp, sp, -96\n");
+    asm.push_str("    sd ra, 88(sp)\n");
+    asm.push_str("    sd a0, 80(sp)\n");
+    asm.push_str("    sd a2, 72(sp)\n");
+    asm.push_str(&format!("    ld t0, {}(a1)\n", (LIMBS - 1) * 8));
+    if !remainder {
+        // 商的符号是两个操作数符号的异或，余数与被除数同号
+        asm.push_str(&format!("    ld t1, {}(a2)\n", (LIMBS - 1) * 8));
+        asm.push_str("    xor t0, t0, t1\n");
+    }
+    asm.push_str("    srli t0, t0, 63\n");
+    asm.push_str("    sd t0, 64(sp)\n");
+    asm.push_str("    mv a0, sp\n");
+    asm.push_str(&format!("    call {I256_ABS}\n"));
+    asm.push_str("    addi a0, sp, 32\n");
+    asm.push_str("    ld a1, 72(sp)\n");
+    asm.push_str(&format!("    call {I256_ABS}\n"));
+    if remainder {
+        asm.push_str("    li a0, 0\n");
+        asm.push_str("    ld a1, 80(sp)\n");
+    } else {
+        asm.push_str("    ld a0, 80(sp)\n");
+        asm.push_str("    li a1, 0\n");
+    }
+    asm.push_str("    mv a2, sp\n");
+    asm.push_str("    addi a3, sp, 32\n");
+    asm.push_str(&format!("    call {U256_DIVMOD}\n"));
+    asm.push_str("    ld t0, 64(sp)\n");
+    asm.push_str(&format!("    beqz t0, {done}\n"));
+    asm.push_str("    ld a0, 80(sp)\n");
+    asm.push_str("    mv a1, a0\n");
+    asm.push_str(&format!("    call {I256_NEG}\n"));
+    asm.push_str(&format!("{done}:\n"));
+    asm.push_str("    ld ra, 88(sp)\n");
+    asm.push_str("    addi sp, sp, 96\n");
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// 比较 lhs 和 rhs（a0 = lhs，a1 = rhs），小于、等于、大于时分别在 a0 中返回 -1、0、1。
+fn compare(name: &str, signed: bool) -> String {
+    let less = format!(".L{name}_less");
+    let greater = format!(".L{name}_greater");
+    let mut asm = header(name);
+    for i in (0..LIMBS).rev() {
+        // 只有最高的字带符号
+        let branch = if signed && i == LIMBS - 1 { "blt" } else { "bltu" };
+        asm.push_str(&format!("    ld t0, {}(a0)\n", i * 8));
+        asm.push_str(&format!("    ld t1, {}(a1)\n", i * 8));
+        asm.push_str(&format!("    {branch} t0, t1, {less}\n"));
+        asm.push_str(&format!("    {branch} t1, t0, {greater}\n"));
+    }
+    asm.push_str("    li a0, 0\n");
+    asm.push_str("    ret\n");
+    asm.push_str(&format!("{less}:\n"));
+    asm.push_str("    li a0, -1\n");
+    asm.push_str("    ret\n");
+    asm.push_str(&format!("{greater}:\n"));
+    asm.push_str("    li a0, 1\n");
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// dest = lhs op rhs（a0 = dest，a1 = lhs，a2 = rhs），逐字进行按位运算。
+fn bitwise(name: &str, op: &str) -> String {
+    let mut asm = header(name);
+    for i in 0..LIMBS {
+        let offset = i * 8;
+        asm.push_str(&format!("    ld t0, {offset}(a1)\n"));
+        asm.push_str(&format!("    ld t1, {offset}(a2)\n"));
+        asm.push_str(&format!("    {op} t0, t0, t1\n"));
+        asm.push_str(&format!("    sd t0, {offset}(a0)\n"));
+    }
+    asm.push_str("    ret\n");
+    asm
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Shift {
+    Left,
+    Right,
+    Arithmetic,
+}
+
+/// dest = value 移位 amount 位（a0 = dest，a1 = value，a2 = amount，amount 也是 256 位整数）。
+///
+/// 值先读入 a3-a6（从低到高），按 amount / 64 整字移动，再在相邻的字之间移动
+/// amount % 64 位。空出的位置填 a7：逻辑移位为 0，算术右移为符号位。
+fn shift(name: &str, kind: Shift) -> String {
+    const VALUE: [&str; 4] = ["a3", "a4", "a5", "a6"];
+    let label = |suffix: &str| format!(".L{name}_{suffix}");
+
+    let mut asm = header(name);
+    for (i, reg) in VALUE.iter().enumerate() {
+        asm.push_str(&format!("    ld {reg}, {}(a1)\n", i * 8));
+    }
+    if kind == Shift::Arithmetic {
+        asm.push_str("    srai a7, a6, 63\n");
+    } else {
+        asm.push_str("    li a7, 0\n");
+    }
+
+    // 移位数不小于 256 时所有字都是填充值
+    asm.push_str("    ld t0, 8(a2)\n");
+    for offset in [16, 24] {
+        asm.push_str(&format!("    ld t1, {offset}(a2)\n"));
+        asm.push_str("    or t0, t0, t1\n");
+    }
+    asm.push_str(&format!("    bnez t0, {}\n", label("saturate")));
+    asm.push_str("    ld t0, 0(a2)\n");
+    asm.push_str("    li t1, 256\n");
+    asm.push_str(&format!("    bgeu t0, t1, {}\n", label("saturate")));
+    asm.push_str("    srli t1, t0, 6\n"); // 整字移动的次数
+    asm.push_str("    andi t0, t0, 63\n"); // 字内移动的位数
+
+    asm.push_str(&format!("{}:\n", label("words")));
+    asm.push_str(&format!("    beqz t1, {}\n", label("bits")));
+    if kind == Shift::Left {
+        for i in (1..VALUE.len()).rev() {
+            asm.push_str(&format!("    mv {}, {}\n", VALUE[i], VALUE[i - 1]));
+        }
+        asm.push_str(&format!("    mv {}, a7\n", VALUE[0]));
+    } else {
+        for i in 0..VALUE.len() - 1 {
+            asm.push_str(&format!("    mv {}, {}\n", VALUE[i], VALUE[i + 1]));
+        }
+        asm.push_str(&format!("    mv {}, a7\n", VALUE[3]));
+    }
+    asm.push_str("    addi t1, t1, -1\n");
+    asm.push_str(&format!("    j {}\n", label("words")));
+
+    // 每个字移入相邻字中被移出的 64 - bits 位，bits 为 0 时跳过，避免移位 64 位
+    asm.push_str(&format!("{}:\n", label("bits")));
+    asm.push_str(&format!("    beqz t0, {}\n", label("store")));
+    asm.push_str("    li t2, 64\n");
+    asm.push_str("    sub t2, t2, t0\n");
+    if kind == Shift::Left {
+        for i in (1..VALUE.len()).rev() {
+            asm.push_str(&format!("    sll {}, {}, t0\n", VALUE[i], VALUE[i]));
+            asm.push_str(&format!("    srl t3, {}, t2\n", VALUE[i - 1]));
+            asm.push_str(&format!("    or {}, {}, t3\n", VALUE[i], VALUE[i]));
+        }
+        asm.push_str(&format!("    sll {}, {}, t0\n", VALUE[0], VALUE[0]));
+    } else {
+        for i in 0..VALUE.len() - 1 {
+            asm.push_str(&format!("    srl {}, {}, t0\n", VALUE[i], VALUE[i]));
+            asm.push_str(&format!("    sll t3, {}, t2\n", VALUE[i + 1]));
+            asm.push_str(&format!("    or {}, {}, t3\n", VALUE[i], VALUE[i]));
+        }
+        let op = if kind == Shift::Arithmetic { "sra" } else { "srl" };
+        asm.push_str(&format!("    {op} {}, {}, t0\n", VALUE[3], VALUE[3]));
+    }
+    asm.push_str(&format!("    j {}\n", label("store")));
+
+    asm.push_str(&format!("{}:\n", label("saturate")));
+    for reg in VALUE {
+        asm.push_str(&format!("    mv {reg}, a7\n"));
+    }
+    asm.push_str(&format!("{}:\n", label("store")));
+    for (i, reg) in VALUE.iter().enumerate() {
+        asm.push_str(&format!("    sd {reg}, {}(a0)\n", i * 8));
+    }
+    asm.push_str("    ret\n");
+    asm
+}
+
+/// 原地翻转 32 字节的字节序（a0 = 地址），在小端序的 256 位整数与大端序编码之间转换。
+fn bswap() -> String {
+    let label = format!(".L{U256_BSWAP}_loop");
+    let done = format!(".L{U256_BSWAP}_done");
+    let mut asm = header(U256_BSWAP);
+    asm.push_str("    addi t0, a0, 31\n");
+    asm.push_str(&format!("{label}:\n"));
+    asm.push_str(&format!("    bgeu a0, t0, {done}\n"));
+    asm.push_str("    lbu t1, 0(a0)\n");
+    asm.push_str("    lbu t2, 0(t0)\n");
+    asm.push_str("    sb t2, 0(a0)\n");
+    asm.push_str("    sb t1, 0(t0)\n");
+    asm.push_str("    addi a0, a0, 1\n");
+    asm.push_str("    addi t0, t0, -1\n");
+    asm.push_str(&format!("    j {label}\n"));
+    asm.push_str(&format!("{done}:\n"));
+    asm.push_str("    ret\n");
+    asm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::IRType;
+
+    #[test]
+    fn test_calls_runtime_for_wide_operands() {
+        let mut func = IRFunction::new("f".to_string());
+        func.params.push(("a".to_string(), IRType::Uint(256)));
+        func.params.push(("b".to_string(), IRType::Int(64)));
+        let a = Value::Local("a".to_string());
+        let b = Value::Local("b".to_string());
+        let one = Value::int(1);
+
+        let wide = func.new_temp(IRType::Uint(256));
+        assert!(calls_runtime(&func, &Instruction::Add(wide.clone(), a.clone(), one.clone())));
+        let cond = func.new_temp(IRType::Bool);
+        assert!(calls_runtime(&func, &Instruction::Lt(cond.clone(), one.clone(), a.clone())));
+        assert!(!calls_runtime(&func, &Instruction::Lt(cond, b.clone(), one.clone())));
+        assert!(!calls_runtime(&func, &Instruction::Move(wide.clone(), a)));
+
+        // 字面量左移一个较窄的值，结果是 256 位
+        assert!(calls_runtime(&func, &Instruction::Shl(wide, one.clone(), b.clone())));
+        let narrow = func.new_temp(IRType::Int(64));
+        assert!(!calls_runtime(&func, &Instruction::Shr(narrow, b, one)));
+    }
+
+    #[test]
+    fn test_source_defines_every_routine() {
+        let asm = source();
+        for name in [
+            U256_ADD, U256_SUB, U256_MUL, U256_DIV, U256_MOD, I256_DIV, I256_MOD, U256_CMP,
+            I256_CMP, U256_AND, U256_OR, U256_XOR, U256_SHL, U256_SHR, I256_SAR, U256_BSWAP,
+            U256_DIVMOD, I256_NEG, I256_ABS,
+        ] {
+            assert!(asm.contains(&format!(".globl {name}\n{name}:\n")), "{name}");
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::backend::riscv::RiscVGenerator;
+use crate::error::CompileError;
+use crate::frontend::lexer::Lexer;
+use crate::frontend::parser::Parser;
+use crate::ir::lower::{lower_contracts, lower_functions};
+use crate::ir::ssa;
+
+/// 把一个 Solidity 源文件编译为 RISC-V 汇编。
+pub fn compile(source: &str) -> Result<String, CompileError> {
+    // 词法分析
+    let mut lexer = Lexer::new(source);
+    let tokens: Vec<_> = lexer.by_ref().collect();
+    if !lexer.errors().is_empty() {
+        return Err(lexer.into_errors().into());
+    }
+
+    // 语法分析
+    let source_unit = Parser::new(tokens).parse()?;
+
+    // 将自由函数和合约中的函数降级为 IR
+    let mut ir_functions = lower_functions(&source_unit.functions)?;
+    ir_functions.extend(lower_contracts(&source_unit.contracts)?);
+
+    // 生成 RISC-V 汇编代码，先添加汇编文件头
+    let mut generator = RiscVGenerator::new();
+    let mut asm = String::new();
+    asm.push_str(".text\n");
+    asm.push_str(".align 2\n\n");
+
+    for mut ir_func in ir_functions {
+        // 构造 SSA 形式，代码生成前再消除 phi
+        ssa::construct(&mut ir_func);
+        ssa::destruct(&mut ir_func);
+
+        let func_asm = generator.generate(&ir_func).map_err(|source| CompileError::Codegen {
+            function: ir_func.name.clone(),
+            source,
+        })?;
+        asm.push_str(&func_asm);
+        asm.push('\n');
+    }
+
+    // 附加用到的运行时例程
+    if let Some(runtime) = generator.runtime() {
+        asm.push_str(&runtime);
+    }
+    Ok(asm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::lexer::LexError;
+    use crate::ir::lower::SemaError;
+
+    #[test]
+    fn test_compile() {
+        let asm = compile("contract C { uint x; function f(uint a) returns (uint) { x = a; return x + 1; } }").unwrap();
+        assert!(asm.starts_with(".text\n"));
+        assert!(asm.contains(".globl C.f\nC.f:\n"));
+    }
+
+    #[test]
+    fn test_compile_mapping_keyed_by_default_address() {
+        let asm = compile("contract C { mapping(address => uint) m; function f() { address a; m[a] = 1; } }").unwrap();
+        assert!(asm.contains("\nC.f:\n"));
+    }
+
+    #[test]
+    fn test_compile_functions_with_same_name() {
+        // 自由函数和各合约中的同名函数、构造函数各有自己的符号
+        let asm = compile(
+            "function f() { }
+            contract A { uint x = 1; function f() { } }
+            contract B { uint y = 2; function f() { } }",
+        )
+        .unwrap();
+        for symbol in ["f", "A.f", "B.f", "A.constructor", "B.constructor"] {
+            assert_eq!(asm.matches(&format!("\n{}:\n", symbol)).count(), 1, "{}", symbol);
+        }
+    }
+
+    #[test]
+    fn test_compile_errors_by_stage() {
+        match compile("function f() { x = 1 @ 2; }") {
+            Err(CompileError::Lex(errors)) => {
+                assert!(matches!(errors[..], [LexError::UnexpectedCharacter { .. }]))
+            }
+            other => panic!("expected a lexical error, got {:?}", other),
+        }
+        match compile("function f() { x = ; } function g( { }") {
+            Err(CompileError::Parse(errors)) => assert_eq!(errors.len(), 2),
+            other => panic!("expected syntax errors, got {:?}", other),
+        }
+        assert!(matches!(
+            compile("function f() { x = 1; }"),
+            Err(CompileError::Sema(SemaError::UndeclaredIdentifier(_)))
+        ));
+        // 字面量不限位数，只检查能否用目标类型表示
+        assert!(compile("function f() { uint x = 99999999999999999999; }").is_ok());
+        assert!(matches!(
+            compile("function f() { uint8 x = 256; }"),
+            Err(CompileError::Sema(SemaError::TypeMismatch(_)))
+        ));
+        // 不支持的类型报告为错误而不是 panic
+        assert!(matches!(
+            compile("function f(bytes32 b) { }"),
+            Err(CompileError::Sema(SemaError::Unsupported(_)))
+        ));
+    }
+
+    #[test]
+    fn test_render_compile_errors() {
+        let source = "function f() {\n    x = ;\n    y = ;\n}";
+        let rendered = compile(source).unwrap_err().render("a.sol", source);
+        assert_eq!(rendered.matches("error[E0001]: Expected expression").count(), 2);
+        assert!(rendered.contains(" --> a.sol:2:9\n"));
+        assert!(rendered.contains("\n\nerror[E0001]"));
+    }
+}
@@ -0,0 +1,87 @@
+use std::io;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::backend::riscv::CodegenError;
+use crate::frontend::lexer::LexError;
+use crate::frontend::parser::ParseError;
+use crate::ir::lower::SemaError;
+
+/// 编译失败的原因，按出错的阶段区分。
+///
+/// 词法和语法分析会一次报告所有错误，语义检查和代码生成在第一个错误处停止。
+#[derive(Debug, Error)]
+pub enum CompileError {
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Found {} lexical error(s)", .0.len())]
+    Lex(Vec<LexError>),
+    #[error("Found {} syntax error(s)", .0.len())]
+    Parse(Vec<ParseError>),
+    #[error(transparent)]
+    Sema(#[from] SemaError),
+    #[error("Code generation failed for function {function}: {source}")]
+    Codegen {
+        function: String,
+        #[source]
+        source: CodegenError,
+    },
+}
+
+impl From<Vec<LexError>> for CompileError {
+    fn from(errors: Vec<LexError>) -> Self {
+        CompileError::Lex(errors)
+    }
+}
+
+impl From<Vec<ParseError>> for CompileError {
+    fn from(errors: Vec<ParseError>) -> Self {
+        CompileError::Parse(errors)
+    }
+}
+
+impl CompileError {
+    /// 渲染给用户看的错误信息。带有位置的错误附上源码片段，每条之间空一行。
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let diagnostics = match self {
+            CompileError::Lex(errors) => errors.iter().map(LexError::to_diagnostic).collect(),
+            CompileError::Parse(errors) => errors.iter().map(|e| e.diagnostic().clone()).collect(),
+            CompileError::Sema(error) => vec![error.diagnostic().clone()],
+            CompileError::Io { .. } | CompileError::Codegen { .. } => return format!("error: {}\n", self),
+        };
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.render(file_name, source))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Value;
+
+    #[test]
+    fn test_render_without_source_location() {
+        let error = CompileError::Codegen {
+            function: "f".to_string(),
+            source: CodegenError::Unallocated(Value::Temporary(3)),
+        };
+        assert_eq!(
+            error.render("a.sol", ""),
+            "error: Code generation failed for function f: No location allocated for Temporary(3)\n"
+        );
+
+        let error = CompileError::Io {
+            path: PathBuf::from("missing.sol"),
+            source: io::Error::new(io::ErrorKind::NotFound, "No such file or directory"),
+        };
+        assert_eq!(error.to_string(), "missing.sol: No such file or directory");
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt;
+
+use crate::frontend::span::{LineIndex, Span};
+
+/// 错误码。E0xxx 为词法和语法错误，E1xxx 为语义错误。
+pub mod codes {
+    pub const UNEXPECTED_TOKEN: &str = "E0001";
+    pub const UNEXPECTED_EOF: &str = "E0002";
+    pub const UNCLOSED_DELIMITER: &str = "E0003";
+    pub const INVALID_LITERAL: &str = "E0004";
+    pub const UNEXPECTED_CHARACTER: &str = "E0005";
+    pub const UNTERMINATED_STRING: &str = "E0006";
+    pub const DUPLICATE_DECLARATION: &str = "E1001";
+    pub const UNDECLARED_IDENTIFIER: &str = "E1002";
+    pub const TYPE_MISMATCH: &str = "E1003";
+    pub const WRONG_ARGUMENT_COUNT: &str = "E1004";
+    pub const INVALID_CONTROL_FLOW: &str = "E1005";
+    pub const CONSTANT_EVALUATION: &str = "E1006";
+    pub const UNSUPPORTED: &str = "E1007";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// 标注源码中的一段区间。主标注指出问题所在，用 `^` 画出；次标注给出相关的位置，用 `-` 画出。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+/// 编译器报告的一条诊断信息。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    pub fn with_secondary_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// 还没有主标注时以 `span` 作为主标注。嵌套的节点逐层调用，最内层的位置优先。
+    pub fn or_label(self, span: Span) -> Self {
+        if self.labels.iter().any(|label| label.primary) {
+            self
+        } else {
+            self.with_label(span, "")
+        }
+    }
+
+    /// 主标注的位置。
+    pub fn span(&self) -> Option<Span> {
+        self.labels.iter().find(|label| label.primary).map(|label| label.span)
+    }
+
+    /// 按 rustc 的格式渲染：标题、位置，以及带有标注的源码行和注释。
+    ///
+    /// ```text
+    /// error[E0001]: Expected ';' after expression
+    ///  --> test.sol:2:11
+    ///   |
+    /// 2 |     x = a b;
+    ///   |           ^ found `b`
+    ///   |
+    ///   = note: ...
+    /// ```
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let lines = LineIndex::new(source);
+        let mut out = format!("{}[{}]: {}\n", self.severity, self.code, self.message);
+
+        let mut labels: Vec<&Label> = self.labels.iter().collect();
+        labels.sort_by_key(|label| (label.span.line, label.span.column, !label.primary));
+        let width = labels
+            .iter()
+            .map(|label| label.span.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = " ".repeat(width);
+
+        if let Some(span) = self.span().or(labels.first().map(|label| label.span)) {
+            out.push_str(&format!("{}--> {}:{}:{}\n", gutter, file_name, span.line, span.column));
+        }
+        if !labels.is_empty() {
+            out.push_str(&format!("{} |\n", gutter));
+        }
+
+        let mut previous_line = None;
+        for label in &labels {
+            let line = label.span.line;
+            let text = lines.line_text(line);
+            if previous_line != Some(line) {
+                if previous_line.is_some_and(|previous| line > previous + 1) {
+                    out.push_str("...\n");
+                }
+                out.push_str(&format!("{:>width$} | {}\n", line, text, width = width));
+                previous_line = Some(line);
+            }
+
+            // 跨行的区间只画到第一行的末尾
+            let line_chars = text.chars().count();
+            let start = (label.span.column - 1).min(line_chars);
+            let length = source
+                .get(label.span.start..label.span.end)
+                .map_or(0, |covered| covered.split('\n').next().unwrap_or("").chars().count())
+                .min(line_chars.saturating_sub(start))
+                .max(1);
+            let marker = if label.primary { "^" } else { "-" };
+            let mut underline = format!("{} | {}{}", gutter, " ".repeat(start), marker.repeat(length));
+            if !label.message.is_empty() {
+                underline.push(' ');
+                underline.push_str(&label.message);
+            }
+            out.push_str(underline.trim_end());
+            out.push('\n');
+        }
+
+        if !self.notes.is_empty() && !labels.is_empty() {
+            out.push_str(&format!("{} |\n", gutter));
+        }
+        for note in &self.notes {
+            out.push_str(&format!("{} = note: {}\n", gutter, note));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)?;
+        if let Some(span) = self.span() {
+            write!(f, " at {}:{}", span.line, span.column)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_labels_and_notes() {
+        let source = "function f() {\n    x = a b;\n}";
+        let lines = LineIndex::new(source);
+        let diagnostic = Diagnostic::error(codes::UNEXPECTED_TOKEN, "Expected ';' after expression")
+            .with_label(lines.span(25, 26), "found `b`")
+            .with_secondary_label(lines.span(13, 14), "in this block")
+            .with_note("statements end with ';'");
+
+        assert_eq!(
+            diagnostic.render("test.sol", source),
+            "error[E0001]: Expected ';' after expression
+ --> test.sol:2:11
+  |
+1 | function f() {
+  |              - in this block
+2 |     x = a b;
+  |           ^ found `b`
+  |
+  = note: statements end with ';'
+"
+        );
+        assert_eq!(diagnostic.to_string(), "error[E0001]: Expected ';' after expression at 2:11");
+    }
+
+    #[test]
+    fn test_render_multiline_span() {
+        let source = "a\n\n\nif (x) {\n  y;\n}";
+        let lines = LineIndex::new(source);
+        let diagnostic = Diagnostic::error(codes::TYPE_MISMATCH, "Condition must be of type bool")
+            .with_secondary_label(lines.span(0, 1), "")
+            .or_label(lines.span(4, source.len()));
+
+        assert_eq!(
+            diagnostic.render("a.sol", source),
+            "error[E1003]: Condition must be of type bool
+ --> a.sol:4:1
+  |
+1 | a
+  | -
+...
+4 | if (x) {
+  | ^^^^^^^^
+"
+        );
+    }
+
+    #[test]
+    fn test_render_without_labels() {
+        let diagnostic = Diagnostic::error(codes::UNSUPPORTED, "Unsupported type").with_note("use uint instead");
+        assert_eq!(
+            diagnostic.render("a.sol", ""),
+            "error[E1007]: Unsupported type\n = note: use uint instead\n"
+        );
+    }
+}
@@ -1,4 +1,8 @@
 use logos::Logos;
+use thiserror::Error;
+
+use crate::frontend::diagnostic::{codes, Diagnostic};
+use crate::frontend::span::{LineIndex, Span};
 
 #[derive(Logos, Debug, PartialEq, Clone)]
 pub enum Token {
@@ -7,6 +11,8 @@ pub enum Token {
     Contract,
     #[token("function")]
     Function,
+    #[token("constructor")]
+    Constructor,
     #[token("returns")]
     Returns,
     #[token("return")]
@@ -21,18 +27,44 @@ pub enum Token {
     Pure,
     #[token("payable")]
     Payable,
+    #[token("mapping")]
+    Mapping,
+    #[token("if")]
+    If,
+    #[token("else")]
+    Else,
+    #[token("for")]
+    For,
+    #[token("while")]
+    While,
+    #[token("do")]
+    Do,
+    #[token("break")]
+    Break,
+    #[token("continue")]
+    Continue,
     
     // 类型
     #[token("uint")]
     Uint,
     #[token("int")]
     Int,
+    // 带位宽的整数类型，位宽为 8 到 256 之间 8 的倍数
+    #[regex(r"uint(8|16|24|32|40|48|56|64|72|80|88|96|104|112|120|128|136|144|152|160|168|176|184|192|200|208|216|224|232|240|248|256)", |lex| lex.slice()[4..].parse().ok())]
+    UintN(u16),
+    #[regex(r"int(8|16|24|32|40|48|56|64|72|80|88|96|104|112|120|128|136|144|152|160|168|176|184|192|200|208|216|224|232|240|248|256)", |lex| lex.slice()[3..].parse().ok())]
+    IntN(u16),
     #[token("bool")]
     Bool,
     #[token("address")]
     Address,
     #[token("string")]
     String,
+    #[token("bytes")]
+    Bytes,
+    // 定长字节数组 bytes1 到 bytes32
+    #[regex(r"bytes(1|2|3|4|5|6|7|8|9|10|11|12|13|14|15|16|17|18|19|20|21|22|23|24|25|26|27|28|29|30|31|32)", |lex| lex.slice()[5..].parse().ok())]
+    BytesN(u8),
     
     // 运算符
     #[token("+")]
@@ -43,6 +75,20 @@ pub enum Token {
     Star,
     #[token("/")]
     Slash,
+    #[token("%")]
+    Percent,
+    #[token("**")]
+    StarStar,
+    #[token("&")]
+    Ampersand,
+    #[token("|")]
+    Pipe,
+    #[token("^")]
+    Caret,
+    #[token("<<")]
+    ShiftLeft,
+    #[token(">>")]
+    ShiftRight,
     #[token("=")]
     Equals,
     #[token("==")]
@@ -57,6 +103,40 @@ pub enum Token {
     GreaterThanOrEqual,
     #[token("<=")]
     LessThanOrEqual,
+    #[token("&&")]
+    LogicalAnd,
+    #[token("||")]
+    LogicalOr,
+    #[token("!")]
+    Not,
+    #[token("~")]
+    Tilde,
+    #[token("++")]
+    Increment,
+    #[token("--")]
+    Decrement,
+    #[token("+=")]
+    PlusEquals,
+    #[token("-=")]
+    MinusEquals,
+    #[token("*=")]
+    StarEquals,
+    #[token("/=")]
+    SlashEquals,
+    #[token("%=")]
+    PercentEquals,
+    #[token("&=")]
+    AmpersandEquals,
+    #[token("|=")]
+    PipeEquals,
+    #[token("^=")]
+    CaretEquals,
+    #[token("<<=")]
+    ShiftLeftEquals,
+    #[token(">>=")]
+    ShiftRightEquals,
+    #[token("=>")]
+    Arrow,
     
     // 分隔符
     #[token("(")]
@@ -67,6 +147,10 @@ pub enum Token {
     LBrace,
     #[token("}")]
     RBrace,
+    #[token("[")]
+    LBracket,
+    #[token("]")]
+    RBracket,
     #[token(";")]
     Semicolon,
     #[token(",")]
@@ -75,7 +159,8 @@ pub enum Token {
     // 字面量
     #[regex(r"[0-9]+")]
     Number,
-    #[regex(r#""[^"]*""#)]
+    // 字符串字面量不能跨行
+    #[regex(r#""[^"\n]*""#)]
     StringLiteral,
     #[regex(r"true|false")]
     Boolean,
@@ -85,50 +170,124 @@ pub enum Token {
     Identifier,
     
     // 空白和注释
-    #[regex(r"[ \t\n\f]+", logos::skip)]
+    #[regex(r"[ \t\r\n\f]+", logos::skip)]
     #[regex(r"//[^\n]*", logos::skip)]
     #[regex(r"/\*[^*]*\*+(?:[^/*][^*]*\*+)*/", logos::skip)]
     Whitespace,
 }
 
+/// 词法错误。
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum LexError {
+    #[error("Unexpected character '{text}'")]
+    UnexpectedCharacter { text: String, span: Span },
+    #[error("Unterminated string literal")]
+    UnterminatedString { span: Span },
+}
+
+impl LexError {
+    pub fn span(&self) -> Span {
+        match self {
+            LexError::UnexpectedCharacter { span, .. } | LexError::UnterminatedString { span } => *span,
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            LexError::UnexpectedCharacter { span, .. } => Diagnostic::error(codes::UNEXPECTED_CHARACTER, self.to_string())
+                .with_label(*span, "not valid in Solidity source"),
+            LexError::UnterminatedString { span } => Diagnostic::error(codes::UNTERMINATED_STRING, self.to_string())
+                .with_label(*span, "missing closing '\"'"),
+        }
+    }
+}
+
+/// 词法分析器，依次产生 token、对应的源码片段及其位置。
+///
+/// 遇到无法识别的字符时记录一条错误并跳过它继续分析，全部错误在分析结束后
+/// 通过 [`Lexer::errors`] 取得。
 pub struct Lexer<'a> {
     inner: logos::Lexer<'a, Token>,
-    peeked: Option<(Token, &'a str)>,
+    lines: LineIndex<'a>,
+    peeked: Option<(Token, &'a str, Span)>,
+    errors: Vec<LexError>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
         Self {
             inner: Token::lexer(input),
+            lines: LineIndex::new(input),
             peeked: None,
+            errors: Vec::new(),
         }
     }
 
+    /// 到目前为止遇到的词法错误。
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<LexError> {
+        self.errors
+    }
+
     #[allow(dead_code)]
-    pub fn peek(&mut self) -> Option<(Token, &'a str)> {
+    pub fn peek(&mut self) -> Option<(Token, &'a str, Span)> {
         if self.peeked.is_none() {
-            self.peeked = match self.inner.next() {
-                Some(Ok(token)) => {
-                    let slice = self.inner.slice();
-                    Some((token, slice))
-                }
-                Some(Err(_)) | None => None,
-            };
+            self.peeked = self.lex();
         }
         self.peeked.clone()
     }
 
-    pub fn next(&mut self) -> Option<(Token, &'a str)> {
-        if let Some(token) = self.peeked.take() {
-            return Some(token);
+    fn lex(&mut self) -> Option<(Token, &'a str, Span)> {
+        loop {
+            let result = self.inner.next()?;
+            let range = self.inner.span();
+            match result {
+                Ok(token) => {
+                    let span = self.lines.span(range.start, range.end);
+                    return Some((token, self.inner.slice(), span));
+                }
+                Err(()) if self.inner.slice().starts_with('"') => self.unterminated_string(range.start),
+                Err(()) => self.unexpected_character(range.start, range.end),
+            }
         }
-        match self.inner.next() {
-            Some(Ok(token)) => {
-                let slice = self.inner.slice();
-                Some((token, slice))
+    }
+
+    /// 没有右引号的字符串字面量。跳过这一行余下的部分，以免把字符串的内容当作 token。
+    fn unterminated_string(&mut self, start: usize) {
+        let rest = self.inner.remainder();
+        self.inner.bump(rest.find('\n').unwrap_or(rest.len()));
+        let span = self.lines.span(start, self.inner.span().end);
+        self.errors.push(LexError::UnterminatedString { span });
+    }
+
+    /// 无法识别的字符，相邻的几个合并为一条错误。
+    fn unexpected_character(&mut self, start: usize, end: usize) {
+        let start = match self.errors.last() {
+            Some(LexError::UnexpectedCharacter { span, .. }) if span.end == start => {
+                let start = span.start;
+                self.errors.pop();
+                start
             }
-            Some(Err(_)) | None => None,
+            _ => start,
+        };
+        self.errors.push(LexError::UnexpectedCharacter {
+            text: self.inner.source()[start..end].to_string(),
+            span: self.lines.span(start, end),
+        });
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = (Token, &'a str, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.peeked.take() {
+            return Some(token);
         }
+        self.lex()
     }
 }
 
@@ -171,6 +330,193 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sized_integer_types() {
+        let tokens: Vec<_> = Lexer::new("uint8 int256 uint uint7 int264 uint8x").collect();
+        let kinds: Vec<_> = tokens.into_iter().map(|(token, _, _)| token).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Token::UintN(8),
+                Token::IntN(256),
+                Token::Uint,
+                Token::Identifier,
+                Token::Identifier,
+                Token::Identifier,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_tokens() {
+        let tokens: Vec<_> = Lexer::new("mapping(bytes32 => bytes) x[] bytes33").collect();
+        let kinds: Vec<_> = tokens.into_iter().map(|(token, _, _)| token).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Token::Mapping,
+                Token::LParen,
+                Token::BytesN(32),
+                Token::Arrow,
+                Token::Bytes,
+                Token::RParen,
+                Token::Identifier,
+                Token::LBracket,
+                Token::RBracket,
+                Token::Identifier,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_control_flow_keywords() {
+        let tokens: Vec<_> = Lexer::new("if else for while do break continue done").collect();
+        let kinds: Vec<_> = tokens.into_iter().map(|(token, _, _)| token).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Token::If,
+                Token::Else,
+                Token::For,
+                Token::While,
+                Token::Do,
+                Token::Break,
+                Token::Continue,
+                Token::Identifier,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let tokens: Vec<_> = Lexer::new("a && b || c == d").collect();
+        let kinds: Vec<_> = tokens.into_iter().map(|(token, _, _)| token).collect();
+        assert_eq!(kinds[1], Token::LogicalAnd);
+        assert_eq!(kinds[3], Token::LogicalOr);
+        assert_eq!(kinds[5], Token::DoubleEquals);
+    }
+
+    #[test]
+    fn test_unary_and_compound_operators() {
+        let tokens: Vec<_> = Lexer::new("!a != ~b ++ -- - += -= *= /= / =").collect();
+        let kinds: Vec<_> = tokens.into_iter().map(|(token, _, _)| token).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Token::Not,
+                Token::Identifier,
+                Token::NotEquals,
+                Token::Tilde,
+                Token::Identifier,
+                Token::Increment,
+                Token::Decrement,
+                Token::Minus,
+                Token::PlusEquals,
+                Token::MinusEquals,
+                Token::StarEquals,
+                Token::SlashEquals,
+                Token::Slash,
+                Token::Equals,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bitwise_and_arithmetic_operators() {
+        let tokens: Vec<_> = Lexer::new("a % b ** c & d && e | f || g ^ h << i <<= j >> k >= l >>= %= &= |= ^=").collect();
+        let kinds: Vec<_> = tokens.into_iter().map(|(token, _, _)| token).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Token::Identifier,
+                Token::Percent,
+                Token::Identifier,
+                Token::StarStar,
+                Token::Identifier,
+                Token::Ampersand,
+                Token::Identifier,
+                Token::LogicalAnd,
+                Token::Identifier,
+                Token::Pipe,
+                Token::Identifier,
+                Token::LogicalOr,
+                Token::Identifier,
+                Token::Caret,
+                Token::Identifier,
+                Token::ShiftLeft,
+                Token::Identifier,
+                Token::ShiftLeftEquals,
+                Token::Identifier,
+                Token::ShiftRight,
+                Token::Identifier,
+                Token::GreaterThanOrEqual,
+                Token::Identifier,
+                Token::ShiftRightEquals,
+                Token::PercentEquals,
+                Token::AmpersandEquals,
+                Token::PipeEquals,
+                Token::CaretEquals,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let tokens: Vec<_> = Lexer::new("function f() {\n  /* 注释 */ return 42;\n}").collect();
+        let spans: Vec<_> = tokens.iter().map(|(_, text, span)| (*text, span.line, span.column)).collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("function", 1, 1),
+                ("f", 1, 10),
+                ("(", 1, 11),
+                (")", 1, 12),
+                ("{", 1, 14),
+                ("return", 2, 12),
+                ("42", 2, 19),
+                (";", 2, 21),
+                ("}", 3, 1),
+            ]
+        );
+        let (_, _, span) = &tokens[6];
+        assert_eq!((span.start, span.end), (37, 39));
+    }
+
+    #[test]
+    fn test_errors_do_not_stop_lexing() {
+        let source = "uint a = 1 @ 2;\nuint b = #$ c;\nstring s = \"open;\nb = a;";
+        let mut lexer = Lexer::new(source);
+        let tokens: Vec<_> = lexer.by_ref().map(|(_, text, _)| text).collect();
+        assert_eq!(
+            tokens,
+            vec!["uint", "a", "=", "1", "2", ";", "uint", "b", "=", "c", ";", "string", "s", "=", "b", "=", "a", ";"]
+        );
+
+        let errors: Vec<_> = lexer
+            .errors()
+            .iter()
+            .map(|e| {
+                let (d, span) = (e.to_diagnostic(), e.span());
+                (d.code, d.message, span.line, span.column, &source[span.start..span.end])
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (codes::UNEXPECTED_CHARACTER, "Unexpected character '@'".to_string(), 1, 12, "@"),
+                (codes::UNEXPECTED_CHARACTER, "Unexpected character '#$'".to_string(), 2, 10, "#$"),
+                (codes::UNTERMINATED_STRING, "Unterminated string literal".to_string(), 3, 12, "\"open;"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let mut lexer = Lexer::new("uint a;\r\nuint b;\r\n");
+        assert_eq!(lexer.by_ref().count(), 6);
+        assert!(lexer.into_errors().is_empty());
+    }
+
     #[test]
     fn test_number_literals() {
         let input = "123 456 0 789";
@@ -178,7 +524,7 @@ mod tests {
         let tokens: Vec<_> = lexer.collect();
 
         assert_eq!(tokens.len(), 4);
-        for (token, _) in tokens {
+        for (token, _, _) in tokens {
             assert_eq!(token, Token::Number);
         }
     }
@@ -190,7 +536,7 @@ mod tests {
         let tokens: Vec<_> = lexer.collect();
 
         assert_eq!(tokens.len(), 3);
-        for (token, _) in tokens {
+        for (token, _, _) in tokens {
             assert_eq!(token, Token::StringLiteral);
         }
     }
@@ -209,7 +555,7 @@ mod tests {
         let tokens: Vec<_> = lexer.collect();
 
         // 注释应该被跳过，不会出现在token流中
-        assert!(tokens.iter().all(|(token, _)| 
+        assert!(tokens.iter().all(|(token, _, _)| 
             matches!(token, 
                 Token::Function | 
                 Token::Identifier | 
@@ -224,11 +570,3 @@ mod tests {
         ));
     }
 }
-
-impl<'a> Iterator for Lexer<'a> {
-    type Item = (Token, &'a str);
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.next()
-    }
-} 
\ No newline at end of file
@@ -1,2 +1,4 @@
+pub mod diagnostic;
 pub mod lexer;
-pub mod parser; 
\ No newline at end of file
+pub mod parser;
+pub mod span;
@@ -1,13 +1,100 @@
+use std::fmt;
+
+use num_bigint::BigUint;
+use thiserror::Error;
+
+use crate::frontend::diagnostic::{codes, Diagnostic};
 use crate::frontend::lexer::Token;
+use crate::frontend::span::Span;
+
+/// 语法错误。按种类区分，各自带有指向源码的诊断信息。
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParseError {
+    #[error("{}", .0.message)]
+    UnexpectedToken(Diagnostic),
+    #[error("{}", .0.message)]
+    UnexpectedEof(Diagnostic),
+    #[error("{}", .0.message)]
+    UnclosedDelimiter(Diagnostic),
+    #[error("{}", .0.message)]
+    InvalidLiteral(Diagnostic),
+    #[error("{}", .0.message)]
+    DuplicateDeclaration(Diagnostic),
+}
+
+impl ParseError {
+    pub fn diagnostic(&self) -> &Diagnostic {
+        match self {
+            ParseError::UnexpectedToken(diagnostic)
+            | ParseError::UnexpectedEof(diagnostic)
+            | ParseError::UnclosedDelimiter(diagnostic)
+            | ParseError::InvalidLiteral(diagnostic)
+            | ParseError::DuplicateDeclaration(diagnostic) => diagnostic,
+        }
+    }
+}
+
+impl From<Diagnostic> for ParseError {
+    /// 按错误码归类。
+    fn from(diagnostic: Diagnostic) -> Self {
+        match diagnostic.code {
+            codes::UNEXPECTED_EOF => ParseError::UnexpectedEof(diagnostic),
+            codes::UNCLOSED_DELIMITER => ParseError::UnclosedDelimiter(diagnostic),
+            codes::INVALID_LITERAL => ParseError::InvalidLiteral(diagnostic),
+            codes::DUPLICATE_DECLARATION => ParseError::DuplicateDeclaration(diagnostic),
+            _ => ParseError::UnexpectedToken(diagnostic),
+        }
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        match error {
+            ParseError::UnexpectedToken(diagnostic)
+            | ParseError::UnexpectedEof(diagnostic)
+            | ParseError::UnclosedDelimiter(diagnostic)
+            | ParseError::InvalidLiteral(diagnostic)
+            | ParseError::DuplicateDeclaration(diagnostic) => diagnostic,
+        }
+    }
+}
+
+/// 表达式及其在源码中的位置。
+#[derive(Debug)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
 
 #[allow(dead_code)]
 #[derive(Debug)]
-pub enum Expr {
-    Number(i64),
+pub enum ExprKind {
+    Number(BigUint), // 不限位数，由降级时按目标类型检查范围
     String(String),
     Boolean(bool),
     Identifier(String),
     BinaryOp(Box<Expr>, BinaryOp, Box<Expr>),
+    Prefix(UnaryOp, Box<Expr>),  // -a, !a, ~a, ++a, --a
+    Postfix(UnaryOp, Box<Expr>), // a++, a--
+    Index(Box<Expr>, Box<Expr>), // base[index]
+    Tuple(Vec<Expr>),            // (a, b, ...)
+    Call(String, Vec<Expr>),     // callee(args...)
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+    BitNot,
+    Increment,
+    Decrement,
 }
 
 #[allow(dead_code)]
@@ -17,174 +104,528 @@ pub enum BinaryOp {
     Subtract,
     Multiply,
     Divide,
+    Modulo,
+    Power,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     Equals,
     NotEquals,
     GreaterThan,
     LessThan,
     GreaterThanOrEqual,
     LessThanOrEqual,
+    LogicalAnd,
+    LogicalOr,
+}
+
+/// 语法树中的类型。
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeName {
+    Uint(u16), // 位宽，uint 即 uint256
+    Int(u16),
+    Bool,
+    Address,
+    String,
+    Bytes,
+    FixedBytes(u8),                        // bytes1 到 bytes32
+    Array(Box<TypeName>, Option<u64>),     // 元素类型，定长数组的长度
+    Mapping(Box<TypeName>, Box<TypeName>), // 键类型，值类型
+    UserDefined(String),
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeName::Uint(bits) => write!(f, "uint{}", bits),
+            TypeName::Int(bits) => write!(f, "int{}", bits),
+            TypeName::Bool => write!(f, "bool"),
+            TypeName::Address => write!(f, "address"),
+            TypeName::String => write!(f, "string"),
+            TypeName::Bytes => write!(f, "bytes"),
+            TypeName::FixedBytes(size) => write!(f, "bytes{}", size),
+            TypeName::Array(element, Some(length)) => write!(f, "{}[{}]", element, length),
+            TypeName::Array(element, None) => write!(f, "{}[]", element),
+            TypeName::Mapping(key, value) => write!(f, "mapping({} => {})", key, value),
+            TypeName::UserDefined(name) => write!(f, "{}", name),
+        }
+    }
 }
 
 #[allow(dead_code)]
 #[derive(Debug)]
 pub struct Function {
     pub name: String,
-    pub params: Vec<(String, String)>, // (type, name)
-    pub returns: Vec<String>,
+    pub params: Vec<(TypeName, String)>, // (type, name)
+    pub returns: Vec<TypeName>,
     pub body: Vec<Statement>,
+    pub span: Span, // 从 function 或 constructor 关键字到函数体的右大括号
+}
+
+/// 语句及其在源码中的位置，包括结尾的分号。
+#[derive(Debug)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self { kind, span }
+    }
 }
 
 #[allow(dead_code)]
 #[derive(Debug)]
-pub enum Statement {
-    VariableDecl(String, String, Option<Expr>), // type, name, initializer
-    Assignment(String, Expr),
+pub enum StatementKind {
+    VariableDecl(TypeName, String, Option<Expr>), // type, name, initializer
+    Assignment(Expr, Expr), // target, value
     Return(Option<Expr>),
+    CompoundAssignment(Expr, BinaryOp, Expr), // target, operator, value：target op= value
+    Expression(Expr),
+    If(Expr, Vec<Statement>, Option<Vec<Statement>>), // condition, then, else
+    While(Expr, Vec<Statement>), // condition, body
+    DoWhile(Vec<Statement>, Expr), // body, condition
+    For(Option<Box<Statement>>, Option<Expr>, Option<Box<Statement>>, Vec<Statement>), // init, condition, update, body
+    Break,
+    Continue,
+    Block(Vec<Statement>), // 嵌套的代码块 { ... }
 }
 
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct StateVariable {
+    pub var_type: TypeName,
+    pub name: String,
+    pub initializer: Option<Expr>,
+    pub span: Span,
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+pub struct Contract {
+    pub name: String,
+    pub state_variables: Vec<StateVariable>,
+    pub constructor: Option<Function>,
+    pub functions: Vec<Function>,
+    pub span: Span, // 合约名
+}
+
+/// 一个源文件的解析结果：合约以及合约之外的自由函数。
+#[derive(Debug, Default)]
+pub struct SourceUnit {
+    pub contracts: Vec<Contract>,
+    pub functions: Vec<Function>,
+}
+
+impl SourceUnit {
+    /// 依次遍历自由函数以及各合约的构造函数和成员函数。
+    pub fn all_functions(&self) -> impl Iterator<Item = &Function> {
+        self.functions.iter().chain(
+            self.contracts
+                .iter()
+                .flat_map(|contract| contract.constructor.iter().chain(contract.functions.iter())),
+        )
+    }
+}
+
+/// 语法分析器。出错时记录错误并跳到下一个分号、右大括号或定义处继续分析，
+/// 一次报告文件中所有的语法错误。
 pub struct Parser<'a> {
-    tokens: Vec<(Token, &'a str)>,
+    tokens: Vec<(Token, &'a str, Span)>,
     current: usize,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: Vec<(Token, &'a str)>) -> Self {
+    pub fn new(tokens: Vec<(Token, &'a str, Span)>) -> Self {
         Self {
             tokens,
             current: 0,
+            diagnostics: Vec::new(),
         }
     }
 
     fn next(&mut self) -> Option<(Token, &'a str)> {
-        if self.current < self.tokens.len() {
-            let token = self.tokens[self.current].clone();
+        let token = self.peek();
+        if token.is_some() {
             self.current += 1;
-            Some(token)
-        } else {
-            None
         }
+        token
     }
 
     fn peek(&self) -> Option<(Token, &'a str)> {
-        if self.current < self.tokens.len() {
-            Some(self.tokens[self.current].clone())
-        } else {
-            None
+        self.tokens
+            .get(self.current)
+            .map(|(token, text, _)| (token.clone(), *text))
+    }
+
+    /// 下一个 token 的位置；已经到达输入末尾时取最后一个 token 的位置。
+    fn peek_span(&self) -> Span {
+        self.tokens
+            .get(self.current)
+            .or(self.tokens.last())
+            .map(|(_, _, span)| *span)
+            .unwrap_or_default()
+    }
+
+    /// 上一个已经消耗的 token 的位置。
+    fn previous_span(&self) -> Span {
+        self.current
+            .checked_sub(1)
+            .and_then(|i| self.tokens.get(i))
+            .map(|(_, _, span)| *span)
+            .unwrap_or_default()
+    }
+
+    /// 从 `start` 到上一个已经消耗的 token 的区间，即刚解析完的节点的位置。
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.previous_span())
+    }
+
+    /// 输入末尾，紧跟在最后一个 token 之后的空区间。
+    fn eof_span(&self) -> Span {
+        match self.tokens.last() {
+            Some((_, text, span)) => Span {
+                start: span.end,
+                end: span.end,
+                column: span.column + text.chars().count(),
+                ..*span
+            },
+            None => Span::default(),
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<Function>, String> {
-        let mut functions = Vec::new();
-        
-        while let Some((token, _)) = self.next() {
+    /// 刚由 `next` 读到的 token `found` 不符合预期。`found` 为 `None` 表示已经到达输入末尾。
+    ///
+    /// 读到的是分号或大括号时退回到它之前：它们决定了语句和代码块的边界，
+    /// 错误恢复要从这里同步，不能把它们当作出错的部分跳过。
+    fn unexpected(&mut self, found: Option<(Token, &str)>, message: impl Into<String>) -> Diagnostic {
+        match found {
+            Some((Token::Semicolon | Token::LBrace | Token::RBrace, _)) => self.current -= 1,
+            Some((_, text)) => {
+                return Diagnostic::error(codes::UNEXPECTED_TOKEN, message)
+                    .with_label(self.previous_span(), format!("found `{}`", text))
+            }
+            None => (),
+        }
+        self.unexpected_next(message)
+    }
+
+    /// 下一个 token 不符合预期，它还没有被消耗。
+    fn unexpected_next(&self, message: impl Into<String>) -> Diagnostic {
+        match self.peek() {
+            Some((_, text)) => Diagnostic::error(codes::UNEXPECTED_TOKEN, message)
+                .with_label(self.peek_span(), format!("found `{}`", text)),
+            None => Diagnostic::error(codes::UNEXPECTED_EOF, message)
+                .with_label(self.eof_span(), "unexpected end of input"),
+        }
+    }
+
+    /// 在下一个 token 处发现 `open` 开始的代码块或合约没有闭合。
+    fn unclosed(&self, open: Span, message: &str) -> Diagnostic {
+        let diagnostic = match self.peek() {
+            Some((_, text)) => Diagnostic::error(codes::UNCLOSED_DELIMITER, message)
+                .with_label(self.peek_span(), format!("found `{}`", text)),
+            None => Diagnostic::error(codes::UNCLOSED_DELIMITER, message)
+                .with_label(self.eof_span(), "unexpected end of input"),
+        };
+        diagnostic.with_secondary_label(open, "unclosed delimiter")
+    }
+
+    /// 语句或合约成员出错后跳过 token，直到消耗了一个分号或一个完整的代码块，
+    /// 或者到达外层代码块的右大括号以及 function、contract、constructor 关键字之前。
+    fn synchronize(&mut self) {
+        let mut depth = 0usize;
+        while let Some((token, _)) = self.peek() {
+            if depth == 0
+                && matches!(token, Token::RBrace | Token::Function | Token::Contract | Token::Constructor)
+            {
+                return;
+            }
+            self.next();
+            match token {
+                Token::Semicolon if depth == 0 => return,
+                Token::LBrace => depth += 1,
+                Token::RBrace => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// 顶层出错后跳到下一个 function 或 contract 定义，其间完整的代码块整个跳过。
+    fn skip_to_definition(&mut self) {
+        let mut depth = 0usize;
+        while let Some((token, _)) = self.peek() {
             match token {
+                Token::Function | Token::Contract if depth == 0 => return,
+                Token::LBrace => depth += 1,
+                Token::RBrace => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            self.next();
+        }
+    }
+
+    /// 解析整个源文件。有语法错误时返回所有的错误。
+    pub fn parse(&mut self) -> Result<SourceUnit, Vec<ParseError>> {
+        let mut unit = SourceUnit::default();
+
+        while let Some((token, _)) = self.peek() {
+            let result = match token {
                 Token::Function => {
-                    if let Some(func) = self.parse_function()? {
-                        functions.push(func);
+                    self.next(); // 消耗 function 关键字
+                    match self.parse_function() {
+                        Ok(Some(func)) => {
+                            unit.functions.push(func);
+                            Ok(())
+                        }
+                        Ok(None) => Err(self.unexpected_next("Expected function name")),
+                        Err(diagnostic) => Err(diagnostic),
                     }
                 }
-                _ => continue,
+                Token::Contract => {
+                    self.next(); // 消耗 contract 关键字
+                    self.parse_contract().map(|contract| unit.contracts.push(contract))
+                }
+                _ => Err(self.unexpected_next("Expected contract or function definition")),
+            };
+            if let Err(diagnostic) = result {
+                self.diagnostics.push(diagnostic);
+                self.skip_to_definition();
             }
         }
-        
-        Ok(functions)
+
+        if self.diagnostics.is_empty() {
+            Ok(unit)
+        } else {
+            Err(self.diagnostics.drain(..).map(ParseError::from).collect())
+        }
     }
 
-    fn parse_function(&mut self) -> Result<Option<Function>, String> {
-        // 解析函数名
+    fn parse_contract(&mut self) -> Result<Contract, Diagnostic> {
         let name = match self.next() {
             Some((Token::Identifier, name)) => name.to_string(),
+            found => return Err(self.unexpected(found, "Expected contract name")),
+        };
+        let span = self.previous_span();
+
+        match self.next() {
+            Some((Token::LBrace, _)) => (),
+            found => return Err(self.unexpected(found, "Expected '{' after contract name")),
+        }
+        let open_brace = self.previous_span();
+
+        let mut contract = Contract {
+            name,
+            state_variables: Vec::new(),
+            constructor: None,
+            functions: Vec::new(),
+            span,
+        };
+
+        loop {
+            match self.peek() {
+                Some((Token::RBrace, _)) => {
+                    self.next(); // 消耗右大括号
+                    break;
+                }
+                Some((Token::Contract, _)) | None => {
+                    let diagnostic = self.unclosed(open_brace, "Expected '}' at end of contract");
+                    self.diagnostics.push(diagnostic);
+                    break;
+                }
+                Some(_) => {
+                    if let Err(diagnostic) = self.parse_member(&mut contract) {
+                        self.diagnostics.push(diagnostic);
+                        self.synchronize();
+                    }
+                }
+            }
+        }
+
+        Ok(contract)
+    }
+
+    /// 解析合约中的一个成员：状态变量、函数或构造函数。
+    fn parse_member(&mut self, contract: &mut Contract) -> Result<(), Diagnostic> {
+        match self.peek() {
+            Some((Token::Function, _)) => {
+                self.next(); // 消耗 function 关键字
+                match self.parse_function()? {
+                    Some(func) => contract.functions.push(func),
+                    None => return Err(self.unexpected_next("Expected function name")),
+                }
+            }
+            Some((Token::Constructor, _)) => {
+                self.next(); // 消耗 constructor 关键字
+                let start = self.previous_span();
+                if let Some(first) = &contract.constructor {
+                    return Err(Diagnostic::error(
+                        codes::DUPLICATE_DECLARATION,
+                        format!("Contract {} has more than one constructor", contract.name),
+                    )
+                    .with_label(start, "second constructor")
+                    .with_secondary_label(first.span, "first constructor declared here"));
+                }
+                contract.constructor = Some(self.parse_function_rest("constructor".to_string(), start)?);
+            }
+            Some(_) if self.at_variable_decl() => {
+                let start = self.peek_span();
+                let var_type = self.parse_type()?;
+                self.skip_attributes();
+
+                let name = match self.next() {
+                    Some((Token::Identifier, name)) => name.to_string(),
+                    found => return Err(self.unexpected(found, "Expected variable name")),
+                };
+                let initializer = self.parse_initializer()?;
+
+                contract.state_variables.push(StateVariable {
+                    var_type,
+                    name,
+                    initializer,
+                    span: self.span_from(start),
+                });
+            }
+            _ => return Err(self.unexpected_next("Expected state variable, function or constructor")),
+        }
+        Ok(())
+    }
+
+    /// 解析 function 关键字之后的部分，关键字已经消耗。
+    fn parse_function(&mut self) -> Result<Option<Function>, Diagnostic> {
+        let start = self.previous_span();
+
+        // 解析函数名，缺少时不消耗下一个 token
+        let name = match self.peek() {
+            Some((Token::Identifier, name)) => {
+                self.next();
+                name.to_string()
+            }
             _ => return Ok(None),
         };
 
+        self.parse_function_rest(name, start).map(Some)
+    }
+
+    /// 解析函数名之后的部分：参数列表、修饰符、返回值和函数体。`start` 是
+    /// function 或 constructor 关键字的位置。
+    fn parse_function_rest(&mut self, name: String, start: Span) -> Result<Function, Diagnostic> {
         // 解析参数列表
         let params = self.parse_params()?;
 
+        // 可见性和状态可变性暂不影响代码生成，直接跳过
+        self.skip_attributes();
+
         // 解析返回值
         let returns = self.parse_returns()?;
 
         // 解析函数体
         let body = self.parse_function_body()?;
 
-        Ok(Some(Function {
+        Ok(Function {
             name,
             params,
             returns,
             body,
-        }))
+            span: self.span_from(start),
+        })
     }
 
-    fn parse_params(&mut self) -> Result<Vec<(String, String)>, String> {
+    /// 跳过可见性（public、private）和状态可变性（view、pure、payable）关键字。
+    fn skip_attributes(&mut self) {
+        while let Some((Token::Public | Token::Private | Token::View | Token::Pure | Token::Payable, _)) =
+            self.peek()
+        {
+            self.next();
+        }
+    }
+
+    /// 解析变量名之后的可选初始化表达式以及结尾的分号。
+    fn parse_initializer(&mut self) -> Result<Option<Expr>, Diagnostic> {
+        match self.next() {
+            Some((Token::Equals, _)) => {
+                let expr = self.parse_expr()?;
+                match self.next() {
+                    Some((Token::Semicolon, _)) => (),
+                    found => return Err(self.unexpected(found, "Expected ';' after variable declaration")),
+                }
+                Ok(Some(expr))
+            }
+            Some((Token::Semicolon, _)) => Ok(None),
+            found => Err(self.unexpected(found, "Expected '=' or ';' after variable name")),
+        }
+    }
+
+    fn parse_params(&mut self) -> Result<Vec<(TypeName, String)>, Diagnostic> {
         let mut params = Vec::new();
         
         // 检查左括号
         match self.next() {
             Some((Token::LParen, _)) => (),
-            _ => return Err("Expected '(' after function name".to_string()),
+            found => return Err(self.unexpected(found, "Expected '(' after function name")),
         }
 
         // 如果下一个 token 是右括号，说明没有参数
         match self.next() {
             Some((Token::RParen, _)) => return Ok(params),
-            Some((token, _)) => {
-                let param_type = match token {
-                    Token::Uint => "uint",
-                    Token::Int => "int",
-                    Token::Bool => "bool",
-                    Token::Address => "address",
-                    Token::String => "string",
-                    _ => return Err("Expected parameter type".to_string()),
-                }.to_string();
+            Some(_) => {
+                self.current -= 1; // 回退，由 parse_type 重新读取类型
+                let param_type = self.parse_type()?;
 
                 match self.next() {
                     Some((Token::Identifier, name)) => {
                         params.push((param_type, name.to_string()));
                     }
-                    _ => return Err("Expected parameter name".to_string()),
+                    found => return Err(self.unexpected(found, "Expected parameter name")),
                 }
             }
-            None => return Err("Expected parameter type".to_string()),
+            None => return Err(self.unexpected(None, "Expected parameter type")),
         }
 
         // 解析更多参数
         loop {
             match self.next() {
                 Some((Token::Comma, _)) => {
-                    match self.next() {
-                        Some((token, _)) => {
-                            let param_type = match token {
-                                Token::Uint => "uint",
-                                Token::Int => "int",
-                                Token::Bool => "bool",
-                                Token::Address => "address",
-                                Token::String => "string",
-                                _ => return Err("Expected parameter type".to_string()),
-                            }.to_string();
+                    match self.peek() {
+                        Some(_) => {
+                            let param_type = self.parse_type()?;
 
                             match self.next() {
                                 Some((Token::Identifier, name)) => {
                                     params.push((param_type, name.to_string()));
                                 }
-                                _ => return Err("Expected parameter name".to_string()),
+                                found => return Err(self.unexpected(found, "Expected parameter name")),
                             }
                         }
-                        None => return Err("Expected parameter type".to_string()),
+                        None => return Err(self.unexpected(None, "Expected parameter type")),
                     }
                 }
                 Some((Token::RParen, _)) => break,
-                _ => return Err("Expected ',' or ')'".to_string()),
+                found => return Err(self.unexpected(found, "Expected ',' or ')'")),
             }
         }
 
         Ok(params)
     }
 
-    fn parse_returns(&mut self) -> Result<Vec<String>, String> {
+    fn parse_returns(&mut self) -> Result<Vec<TypeName>, Diagnostic> {
         let mut returns = Vec::new();
         
         // 检查 returns 关键字
-        match self.next() {
-            Some((Token::Returns, _)) => (),
+        match self.peek() {
+            Some((Token::Returns, _)) => {
+                self.next(); // 消耗 returns 关键字
+            }
             Some(_) => return Ok(returns), // 如果不是 returns，说明没有返回值
             None => return Ok(returns),    // 如果到达输入末尾，也说明没有返回值
         }
@@ -192,204 +633,925 @@ impl<'a> Parser<'a> {
         // 检查左括号
         match self.next() {
             Some((Token::LParen, _)) => (),
-            _ => return Err("Expected '(' after returns".to_string()),
+            found => return Err(self.unexpected(found, "Expected '(' after returns")),
         }
 
         loop {
             // 解析返回类型
-            let return_type = match self.next() {
-                Some((Token::Uint, _)) => "uint".to_string(),
-                Some((Token::Int, _)) => "int".to_string(),
-                Some((Token::Bool, _)) => "bool".to_string(),
-                Some((Token::Address, _)) => "address".to_string(),
-                Some((Token::String, _)) => "string".to_string(),
+            if let Some((Token::RParen, _)) = self.peek() {
+                self.next();
+                break;
+            }
+            returns.push(self.parse_type()?);
+
+            // 检查是否有更多返回类型
+            match self.next() {
+                Some((Token::Comma, _)) => continue,
                 Some((Token::RParen, _)) => break,
-                _ => return Err("Expected return type".to_string()),
-            };
+                found => return Err(self.unexpected(found, "Expected ',' or ')'")),
+            }
+        }
 
-            if !return_type.is_empty() {
-                returns.push(return_type);
+        Ok(returns)
+    }
 
-                // 检查是否有更多返回类型
+    /// 解析类型：基本类型、映射或用户定义的类型名，后面可以跟若干数组后缀。
+    fn parse_type(&mut self) -> Result<TypeName, Diagnostic> {
+        let mut ty = match self.next() {
+            Some((Token::Uint, _)) => TypeName::Uint(256),
+            Some((Token::Int, _)) => TypeName::Int(256),
+            Some((Token::UintN(bits), _)) => TypeName::Uint(bits),
+            Some((Token::IntN(bits), _)) => TypeName::Int(bits),
+            Some((Token::Bool, _)) => TypeName::Bool,
+            Some((Token::Address, _)) => TypeName::Address,
+            Some((Token::String, _)) => TypeName::String,
+            Some((Token::Bytes, _)) => TypeName::Bytes,
+            Some((Token::BytesN(size), _)) => TypeName::FixedBytes(size),
+            Some((Token::Identifier, name)) => TypeName::UserDefined(name.to_string()),
+            Some((Token::Mapping, _)) => {
                 match self.next() {
-                    Some((Token::Comma, _)) => continue,
-                    Some((Token::RParen, _)) => break,
-                    _ => return Err("Expected ',' or ')'".to_string()),
+                    Some((Token::LParen, _)) => (),
+                    found => return Err(self.unexpected(found, "Expected '(' after mapping")),
                 }
+                let key = self.parse_type()?;
+                match self.next() {
+                    Some((Token::Arrow, _)) => (),
+                    found => return Err(self.unexpected(found, "Expected '=>' in mapping type")),
+                }
+                let value = self.parse_type()?;
+                match self.next() {
+                    Some((Token::RParen, _)) => (),
+                    found => return Err(self.unexpected(found, "Expected ')' after mapping value type")),
+                }
+                TypeName::Mapping(Box::new(key), Box::new(value))
             }
+            found => return Err(self.unexpected(found, "Expected type")),
+        };
+
+        // 数组后缀：T[] 或 T[N]
+        while let Some((Token::LBracket, _)) = self.peek() {
+            self.next();
+            let length = match self.next() {
+                Some((Token::RBracket, _)) => None,
+                Some((Token::Number, n)) => {
+                    let length = n.parse().map_err(|_| {
+                        Diagnostic::error(codes::INVALID_LITERAL, format!("Invalid array length: {}", n))
+                            .with_label(self.previous_span(), "array length is too large")
+                    })?;
+                    match self.next() {
+                        Some((Token::RBracket, _)) => (),
+                        found => return Err(self.unexpected(found, "Expected ']' after array length")),
+                    }
+                    Some(length)
+                }
+                found => return Err(self.unexpected(found, "Expected array length or ']'")),
+            };
+            ty = TypeName::Array(Box::new(ty), length);
         }
 
-        Ok(returns)
+        Ok(ty)
     }
 
-    fn parse_function_body(&mut self) -> Result<Vec<Statement>, String> {
-        let mut statements = Vec::new();
-        
+    /// 当前语句是否为变量声明。以标识符开头时既可能是用户定义的类型（`Point[] ps;`），
+    /// 也可能是赋值的目标（`balances[a] = 1;`），试着解析出类型后看后面是否跟着变量名。
+    fn at_variable_decl(&mut self) -> bool {
+        match self.peek() {
+            Some((Token::Identifier, _)) => {
+                let start = self.current;
+                let is_decl = self.parse_type().is_ok()
+                    && matches!(
+                        self.peek(),
+                        Some((Token::Identifier | Token::Public | Token::Private, _))
+                    );
+                self.current = start;
+                is_decl
+            }
+            Some((token, _)) => matches!(
+                token,
+                Token::Uint
+                    | Token::Int
+                    | Token::UintN(_)
+                    | Token::IntN(_)
+                    | Token::Bool
+                    | Token::Address
+                    | Token::String
+                    | Token::Bytes
+                    | Token::BytesN(_)
+                    | Token::Mapping
+            ),
+            None => false,
+        }
+    }
+
+    fn parse_function_body(&mut self) -> Result<Vec<Statement>, Diagnostic> {
         // 检查左大括号
         match self.next() {
             Some((Token::LBrace, _)) => (),
-            _ => return Err("Expected '{' at start of function body".to_string()),
+            found => return Err(self.unexpected(found, "Expected '{' at start of function body")),
         }
 
+        Ok(self.parse_block_rest())
+    }
+
+    /// 解析左大括号之后的语句，直到匹配的右大括号。语句中的错误记录下来后
+    /// 跳到下一条语句继续，因此总能得到一个代码块。
+    fn parse_block_rest(&mut self) -> Vec<Statement> {
+        let open_brace = self.previous_span();
+        let mut statements = Vec::new();
+
         loop {
             match self.peek() {
                 Some((Token::RBrace, _)) => {
                     self.next(); // 消耗右大括号
                     break;
                 }
-                Some((Token::Return, _)) => {
-                    self.next(); // 消耗 return 关键字
-                    let expr = match self.peek() {
-                        Some((Token::Semicolon, _)) => {
-                            self.next(); // 消耗分号
-                            None
-                        }
-                        _ => {
-                            let expr = self.parse_expr()?;
-                            match self.next() {
-                                Some((Token::Semicolon, _)) => (),
-                                _ => return Err("Expected ';' after return expression".to_string()),
-                            }
-                            Some(expr)
-                        }
-                    };
-                    statements.push(Statement::Return(expr));
+                // 代码块中不会出现新的定义，说明缺少右大括号
+                Some((Token::Function | Token::Contract | Token::Constructor, _)) | None => {
+                    let diagnostic = self.unclosed(open_brace, "Expected '}' at end of block");
+                    self.diagnostics.push(diagnostic);
+                    break;
                 }
-                Some((Token::Uint, _)) | Some((Token::Int, _)) | Some((Token::Bool, _)) |
-                Some((Token::Address, _)) | Some((Token::String, _)) => {
-                    let type_token = self.next().unwrap(); // 消耗类型
-                    let var_type = match type_token {
-                        (Token::Uint, _) => "uint",
-                        (Token::Int, _) => "int",
-                        (Token::Bool, _) => "bool",
-                        (Token::Address, _) => "address",
-                        (Token::String, _) => "string",
-                        _ => return Err("Expected variable type".to_string()),
-                    }.to_string();
-
-                    let var_name = match self.next() {
-                        Some((Token::Identifier, name)) => name.to_string(),
-                        _ => return Err("Expected variable name".to_string()),
-                    };
-
-                    let initializer = match self.next() {
-                        Some((Token::Equals, _)) => {
-                            let expr = self.parse_expr()?;
-                            match self.next() {
-                                Some((Token::Semicolon, _)) => (),
-                                _ => return Err("Expected ';' after variable declaration".to_string()),
-                            }
-                            Some(expr)
-                        }
-                        Some((Token::Semicolon, _)) => None,
-                        _ => return Err("Expected '=' or ';' after variable name".to_string()),
-                    };
+                Some(_) => match self.parse_statement() {
+                    Ok(statement) => statements.push(statement),
+                    Err(diagnostic) => {
+                        self.diagnostics.push(diagnostic);
+                        self.synchronize();
+                    }
+                },
+            }
+        }
 
-                    statements.push(Statement::VariableDecl(var_type, var_name, initializer));
-                }
-                Some((Token::Identifier, _)) => {
-                    let name = match self.next() {
-                        Some((Token::Identifier, name)) => name.to_string(),
-                        _ => return Err("Expected identifier".to_string()),
-                    };
+        statements
+    }
 
-                    match self.next() {
-                        Some((Token::Equals, _)) => {
-                            let expr = self.parse_expr()?;
-                            match self.next() {
-                                Some((Token::Semicolon, _)) => (),
-                                _ => return Err("Expected ';' after assignment".to_string()),
-                            }
-                            statements.push(Statement::Assignment(name, expr));
+    /// 消耗一个给定的 token，否则报告 `message`。
+    fn expect(&mut self, token: Token, message: &str) -> Result<(), Diagnostic> {
+        match self.next() {
+            Some((next, _)) if next == token => Ok(()),
+            found => Err(self.unexpected(found, message)),
+        }
+    }
+
+    /// 解析 `keyword` 之后括号中的条件。
+    fn parse_condition(&mut self, keyword: &str) -> Result<Expr, Diagnostic> {
+        self.expect(Token::LParen, &format!("Expected '(' after '{}'", keyword))?;
+        let condition = self.parse_expr()?;
+        self.expect(Token::RParen, &format!("Expected ')' after {} condition", keyword))?;
+        Ok(condition)
+    }
+
+    /// 解析 if/else 的分支或循环体：一个代码块或者单条语句。
+    fn parse_branch(&mut self) -> Result<Vec<Statement>, Diagnostic> {
+        match self.peek() {
+            Some((Token::LBrace, _)) => {
+                self.next(); // 消耗左大括号
+                Ok(self.parse_block_rest())
+            }
+            _ => Ok(vec![self.parse_statement()?]),
+        }
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement, Diagnostic> {
+        let start = self.peek_span();
+        let kind = self.parse_statement_kind()?;
+        Ok(Statement::new(kind, self.span_from(start)))
+    }
+
+    fn parse_statement_kind(&mut self) -> Result<StatementKind, Diagnostic> {
+        match self.peek() {
+            Some((Token::Return, _)) => {
+                self.next(); // 消耗 return 关键字
+                let expr = match self.peek() {
+                    Some((Token::Semicolon, _)) => {
+                        self.next(); // 消耗分号
+                        None
+                    }
+                    _ => {
+                        let expr = self.parse_expr()?;
+                        match self.next() {
+                            Some((Token::Semicolon, _)) => (),
+                            found => return Err(self.unexpected(found, "Expected ';' after return expression")),
                         }
-                        _ => return Err("Expected '=' after identifier".to_string()),
+                        Some(expr)
                     }
-                }
-                _ => return Err("Expected statement".to_string()),
+                };
+                Ok(StatementKind::Return(expr))
+            }
+            Some((Token::If, _)) => {
+                self.next(); // 消耗 if 关键字
+                let condition = self.parse_condition("if")?;
+                let then_branch = self.parse_branch()?;
+                // else if 作为 else 分支中唯一的一条 if 语句
+                let else_branch = match self.peek() {
+                    Some((Token::Else, _)) => {
+                        self.next(); // 消耗 else 关键字
+                        Some(self.parse_branch()?)
+                    }
+                    _ => None,
+                };
+
+                Ok(StatementKind::If(condition, then_branch, else_branch))
+            }
+            Some((Token::While, _)) => {
+                self.next(); // 消耗 while 关键字
+                let condition = self.parse_condition("while")?;
+                let body = self.parse_branch()?;
+                Ok(StatementKind::While(condition, body))
             }
+            Some((Token::Do, _)) => {
+                self.next(); // 消耗 do 关键字
+                let body = self.parse_branch()?;
+                self.expect(Token::While, "Expected 'while' after do-while body")?;
+                let condition = self.parse_condition("while")?;
+                self.expect(Token::Semicolon, "Expected ';' after do-while condition")?;
+                Ok(StatementKind::DoWhile(body, condition))
+            }
+            Some((Token::For, _)) => {
+                self.next(); // 消耗 for 关键字
+                self.expect(Token::LParen, "Expected '(' after 'for'")?;
+
+                // 初始化部分只能是变量声明或赋值，它们自带分号
+                let init_start = self.peek_span();
+                let init = match self.peek() {
+                    Some((Token::Semicolon, _)) => {
+                        self.next();
+                        None
+                    }
+                    _ => match self.parse_statement()? {
+                        stmt @ Statement {
+                            kind:
+                                StatementKind::VariableDecl(..)
+                                | StatementKind::Assignment(..)
+                                | StatementKind::CompoundAssignment(..)
+                                | StatementKind::Expression(_),
+                            ..
+                        } => Some(Box::new(stmt)),
+                        _ => {
+                            return Err(Diagnostic::error(
+                                codes::UNEXPECTED_TOKEN,
+                                "Expected variable declaration or expression in for loop",
+                            )
+                            .with_label(init_start, "not allowed in a for loop header"))
+                        }
+                    },
+                };
+                let condition = match self.peek() {
+                    Some((Token::Semicolon, _)) => None,
+                    _ => Some(self.parse_expr()?),
+                };
+                self.expect(Token::Semicolon, "Expected ';' after for loop condition")?;
+                let update = match self.peek() {
+                    Some((Token::RParen, _)) => None,
+                    _ => Some(Box::new(self.parse_simple_statement()?)),
+                };
+                self.expect(Token::RParen, "Expected ')' after for loop header")?;
+
+                let body = self.parse_branch()?;
+                Ok(StatementKind::For(init, condition, update, body))
+            }
+            Some((Token::LBrace, _)) => {
+                self.next(); // 消耗左大括号
+                Ok(StatementKind::Block(self.parse_block_rest()))
+            }
+            Some((Token::Break, _)) => {
+                self.next(); // 消耗 break 关键字
+                self.expect(Token::Semicolon, "Expected ';' after 'break'")?;
+                Ok(StatementKind::Break)
+            }
+            Some((Token::Continue, _)) => {
+                self.next(); // 消耗 continue 关键字
+                self.expect(Token::Semicolon, "Expected ';' after 'continue'")?;
+                Ok(StatementKind::Continue)
+            }
+            Some(_) if self.at_variable_decl() => {
+                let var_type = self.parse_type()?;
+
+                let var_name = match self.next() {
+                    Some((Token::Identifier, name)) => name.to_string(),
+                    found => return Err(self.unexpected(found, "Expected variable name")),
+                };
+
+                let initializer = self.parse_initializer()?;
+
+                Ok(StatementKind::VariableDecl(var_type, var_name, initializer))
+            }
+            Some((Token::Identifier | Token::Increment | Token::Decrement, _)) => {
+                let statement = self.parse_simple_statement()?;
+                self.expect(Token::Semicolon, "Expected ';' after expression")?;
+                Ok(statement.kind)
+            }
+            _ => Err(self.unexpected_next("Expected statement")),
         }
+    }
+
+    /// 解析不带分号的赋值、复合赋值或表达式语句，也用于 for 循环的更新部分。
+    fn parse_simple_statement(&mut self) -> Result<Statement, Diagnostic> {
+        let target = self.parse_expr()?;
+        let start = target.span;
 
-        Ok(statements)
+        let op = match self.peek() {
+            Some((Token::Equals, _)) => None,
+            Some((Token::PlusEquals, _)) => Some(BinaryOp::Add),
+            Some((Token::MinusEquals, _)) => Some(BinaryOp::Subtract),
+            Some((Token::StarEquals, _)) => Some(BinaryOp::Multiply),
+            Some((Token::SlashEquals, _)) => Some(BinaryOp::Divide),
+            Some((Token::PercentEquals, _)) => Some(BinaryOp::Modulo),
+            Some((Token::AmpersandEquals, _)) => Some(BinaryOp::BitAnd),
+            Some((Token::PipeEquals, _)) => Some(BinaryOp::BitOr),
+            Some((Token::CaretEquals, _)) => Some(BinaryOp::BitXor),
+            Some((Token::ShiftLeftEquals, _)) => Some(BinaryOp::ShiftLeft),
+            Some((Token::ShiftRightEquals, _)) => Some(BinaryOp::ShiftRight),
+            _ => return Ok(Statement::new(StatementKind::Expression(target), start)),
+        };
+        self.next(); // 消耗赋值运算符
+        let expr = self.parse_expr()?;
+
+        let kind = match op {
+            Some(op) => StatementKind::CompoundAssignment(target, op, expr),
+            None => StatementKind::Assignment(target, expr),
+        };
+        Ok(Statement::new(kind, self.span_from(start)))
     }
 
-    fn parse_expr(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_term()?;
+    fn parse_expr(&mut self) -> Result<Expr, Diagnostic> {
+        self.parse_binary(1)
+    }
 
-        loop {
-            match self.peek() {
-                Some((Token::Plus, _)) => {
-                    self.next(); // 消耗运算符
-                    let right = self.parse_term()?;
-                    left = Expr::BinaryOp(Box::new(left), BinaryOp::Add, Box::new(right));
-                }
-                Some((Token::Minus, _)) => {
-                    self.next(); // 消耗运算符
-                    let right = self.parse_term()?;
-                    left = Expr::BinaryOp(Box::new(left), BinaryOp::Subtract, Box::new(right));
-                }
-                Some((Token::GreaterThan, _)) => {
-                    self.next(); // 消耗运算符
-                    let right = self.parse_term()?;
-                    left = Expr::BinaryOp(Box::new(left), BinaryOp::GreaterThan, Box::new(right));
-                }
-                Some((Token::LessThan, _)) => {
-                    self.next(); // 消耗运算符
-                    let right = self.parse_term()?;
-                    left = Expr::BinaryOp(Box::new(left), BinaryOp::LessThan, Box::new(right));
-                }
-                Some((Token::GreaterThanOrEqual, _)) => {
-                    self.next(); // 消耗运算符
-                    let right = self.parse_term()?;
-                    left = Expr::BinaryOp(Box::new(left), BinaryOp::GreaterThanOrEqual, Box::new(right));
-                }
-                Some((Token::LessThanOrEqual, _)) => {
-                    self.next(); // 消耗运算符
-                    let right = self.parse_term()?;
-                    left = Expr::BinaryOp(Box::new(left), BinaryOp::LessThanOrEqual, Box::new(right));
-                }
-                _ => break,
+    /// 按优先级爬升解析二元运算，只处理优先级不低于 `min_precedence` 的运算符。
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<Expr, Diagnostic> {
+        let mut left = self.parse_unary()?;
+
+        while let Some((op, precedence)) = self.peek().and_then(|(token, _)| binary_operator(&token)) {
+            if precedence < min_precedence {
+                break;
             }
+            self.next(); // 消耗运算符
+            // 左结合：右侧只接受优先级更高的运算；** 右结合，右侧接受同级运算
+            let right = match op {
+                BinaryOp::Power => self.parse_binary(precedence)?,
+                _ => self.parse_binary(precedence + 1)?,
+            };
+            let span = left.span.to(right.span);
+            left = Expr::new(ExprKind::BinaryOp(Box::new(left), op, Box::new(right)), span);
         }
 
         Ok(left)
     }
 
-    fn parse_term(&mut self) -> Result<Expr, String> {
-        let mut left = self.parse_factor()?;
+    /// 前缀运算符比所有二元运算符结合得更紧密。
+    fn parse_unary(&mut self) -> Result<Expr, Diagnostic> {
+        let op = match self.peek() {
+            Some((Token::Minus, _)) => UnaryOp::Negate,
+            Some((Token::Not, _)) => UnaryOp::Not,
+            Some((Token::Tilde, _)) => UnaryOp::BitNot,
+            Some((Token::Increment, _)) => UnaryOp::Increment,
+            Some((Token::Decrement, _)) => UnaryOp::Decrement,
+            _ => return self.parse_factor(),
+        };
+        let start = self.peek_span();
+        self.next(); // 消耗运算符
+        let operand = self.parse_unary()?;
+        let span = start.to(operand.span);
+        Ok(Expr::new(ExprKind::Prefix(op, Box::new(operand)), span))
+    }
+
+    fn parse_factor(&mut self) -> Result<Expr, Diagnostic> {
+        let mut expr = self.parse_primary()?;
+
+        // 下标访问：a[i][j]
+        while let Some((Token::LBracket, _)) = self.peek() {
+            self.next();
+            let index = self.parse_expr()?;
+            match self.next() {
+                Some((Token::RBracket, _)) => (),
+                found => return Err(self.unexpected(found, "Expected ']' after index")),
+            }
+            let span = self.span_from(expr.span);
+            expr = Expr::new(ExprKind::Index(Box::new(expr), Box::new(index)), span);
+        }
 
+        // 后缀自增自减：a++、m[k]--
         loop {
-            match self.peek() {
-                Some((Token::Star, _)) => {
-                    self.next(); // 消耗运算符
-                    let right = self.parse_factor()?;
-                    left = Expr::BinaryOp(Box::new(left), BinaryOp::Multiply, Box::new(right));
-                }
-                Some((Token::Slash, _)) => {
-                    self.next(); // 消耗运算符
-                    let right = self.parse_factor()?;
-                    left = Expr::BinaryOp(Box::new(left), BinaryOp::Divide, Box::new(right));
-                }
+            let op = match self.peek() {
+                Some((Token::Increment, _)) => UnaryOp::Increment,
+                Some((Token::Decrement, _)) => UnaryOp::Decrement,
                 _ => break,
-            }
+            };
+            self.next();
+            let span = self.span_from(expr.span);
+            expr = Expr::new(ExprKind::Postfix(op, Box::new(expr)), span);
         }
 
-        Ok(left)
+        Ok(expr)
     }
 
-    fn parse_factor(&mut self) -> Result<Expr, String> {
-        match self.next() {
-            Some((Token::Number, n)) => Ok(Expr::Number(n.parse().unwrap())),
-            Some((Token::StringLiteral, s)) => Ok(Expr::String(s.to_string())),
-            Some((Token::Boolean, b)) => Ok(Expr::Boolean(b.parse().unwrap())),
-            Some((Token::Identifier, name)) => Ok(Expr::Identifier(name.to_string())),
+    fn parse_primary(&mut self) -> Result<Expr, Diagnostic> {
+        let start = self.peek_span();
+        let kind = match self.next() {
+            Some((Token::Number, n)) => ExprKind::Number(n.parse().map_err(|_| {
+                Diagnostic::error(codes::INVALID_LITERAL, format!("Invalid number literal: {}", n))
+                    .with_label(start, "not a decimal number")
+            })?),
+            Some((Token::StringLiteral, s)) => ExprKind::String(s.to_string()),
+            Some((Token::Boolean, b)) => ExprKind::Boolean(b.parse().unwrap()),
+            Some((Token::Identifier, name)) => match self.peek() {
+                Some((Token::LParen, _)) => {
+                    self.next();
+                    ExprKind::Call(name.to_string(), self.parse_arguments()?)
+                }
+                _ => ExprKind::Identifier(name.to_string()),
+            },
             Some((Token::LParen, _)) => {
                 let expr = self.parse_expr()?;
-                match self.next() {
-                    Some((Token::RParen, _)) => Ok(expr),
-                    _ => Err("Expected ')'".to_string()),
+                let mut elements = vec![expr];
+                loop {
+                    match self.next() {
+                        Some((Token::Comma, _)) => elements.push(self.parse_expr()?),
+                        Some((Token::RParen, _)) => break,
+                        found => return Err(self.unexpected(found, "Expected ')'")),
+                    }
+                }
+                if elements.len() == 1 {
+                    // 括号中的单个表达式，位置包括括号
+                    elements.remove(0).kind
+                } else {
+                    ExprKind::Tuple(elements)
                 }
             }
-            _ => Err("Expected expression".to_string()),
+            found => return Err(self.unexpected(found, "Expected expression")),
+        };
+        Ok(Expr::new(kind, self.span_from(start)))
+    }
+
+    /// 解析调用的参数列表，左括号已经消耗。
+    fn parse_arguments(&mut self) -> Result<Vec<Expr>, Diagnostic> {
+        let mut args = Vec::new();
+        if let Some((Token::RParen, _)) = self.peek() {
+            self.next();
+            return Ok(args);
         }
+        loop {
+            args.push(self.parse_expr()?);
+            match self.next() {
+                Some((Token::Comma, _)) => continue,
+                Some((Token::RParen, _)) => return Ok(args),
+                found => return Err(self.unexpected(found, "Expected ',' or ')' in argument list")),
+            }
+        }
+    }
+}
+
+/// 二元运算符及其优先级，数值越大结合越紧密，与 Solidity 的优先级表一致。
+fn binary_operator(token: &Token) -> Option<(BinaryOp, u8)> {
+    match token {
+        Token::LogicalOr => Some((BinaryOp::LogicalOr, 1)),
+        Token::LogicalAnd => Some((BinaryOp::LogicalAnd, 2)),
+        Token::DoubleEquals => Some((BinaryOp::Equals, 3)),
+        Token::NotEquals => Some((BinaryOp::NotEquals, 3)),
+        Token::LessThan => Some((BinaryOp::LessThan, 4)),
+        Token::GreaterThan => Some((BinaryOp::GreaterThan, 4)),
+        Token::LessThanOrEqual => Some((BinaryOp::LessThanOrEqual, 4)),
+        Token::GreaterThanOrEqual => Some((BinaryOp::GreaterThanOrEqual, 4)),
+        Token::Pipe => Some((BinaryOp::BitOr, 5)),
+        Token::Caret => Some((BinaryOp::BitXor, 6)),
+        Token::Ampersand => Some((BinaryOp::BitAnd, 7)),
+        Token::ShiftLeft => Some((BinaryOp::ShiftLeft, 8)),
+        Token::ShiftRight => Some((BinaryOp::ShiftRight, 8)),
+        Token::Plus => Some((BinaryOp::Add, 9)),
+        Token::Minus => Some((BinaryOp::Subtract, 9)),
+        Token::Star => Some((BinaryOp::Multiply, 10)),
+        Token::Slash => Some((BinaryOp::Divide, 10)),
+        Token::Percent => Some((BinaryOp::Modulo, 10)),
+        Token::StarStar => Some((BinaryOp::Power, 11)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::lexer::Lexer;
+
+    fn parse(input: &str) -> Result<Vec<Function>, Vec<ParseError>> {
+        parse_unit(input).map(|unit| unit.functions)
+    }
+
+    fn parse_unit(input: &str) -> Result<SourceUnit, Vec<ParseError>> {
+        let tokens: Vec<_> = Lexer::new(input).collect();
+        Parser::new(tokens).parse()
+    }
+
+    #[test]
+    fn test_parse_types() {
+        let functions = parse(
+            "function f(uint8 a, mapping(address => mapping(uint => bool)) m, bytes32[4][] b) returns (int, bytes) { }",
+        )
+        .unwrap();
+        let func = &functions[0];
+
+        assert_eq!(func.params[0].0, TypeName::Uint(8));
+        assert_eq!(
+            func.params[1].0,
+            TypeName::Mapping(
+                Box::new(TypeName::Address),
+                Box::new(TypeName::Mapping(Box::new(TypeName::Uint(256)), Box::new(TypeName::Bool))),
+            )
+        );
+        assert_eq!(
+            func.params[2].0,
+            TypeName::Array(
+                Box::new(TypeName::Array(Box::new(TypeName::FixedBytes(32)), Some(4))),
+                None,
+            )
+        );
+        assert_eq!(func.params[2].0.to_string(), "bytes32[4][]");
+        assert_eq!(func.returns, vec![TypeName::Int(256), TypeName::Bytes]);
+    }
+
+    #[test]
+    fn test_parse_user_defined_declaration() {
+        let functions = parse("function f() { Point p; Point[] ps; p = q; }").unwrap();
+        let body = &functions[0].body;
+
+        assert!(matches!(&body[0], Statement { kind: StatementKind::VariableDecl(TypeName::UserDefined(t), n, None), .. } if t == "Point" && n == "p"));
+        assert!(matches!(
+            &body[1],
+            Statement { kind: StatementKind::VariableDecl(TypeName::Array(element, None), n, None), .. }
+                if **element == TypeName::UserDefined("Point".to_string()) && n == "ps"
+        ));
+        assert!(matches!(&body[2], Statement { kind: StatementKind::Assignment(Expr { kind: ExprKind::Identifier(n), .. }, _), .. } if n == "p"));
+    }
+
+    #[test]
+    fn test_parse_index_assignment() {
+        let functions = parse("function f() { m[a][1] = m[b][2] + 1; Point[2] ps; ps[1] = p; }").unwrap();
+        let body = &functions[0].body;
+
+        let Statement { kind: StatementKind::Assignment(Expr { kind: ExprKind::Index(base, index), .. }, Expr { kind: ExprKind::BinaryOp(lhs, BinaryOp::Add, _), .. }), .. } = &body[0] else {
+            panic!("unexpected statement: {:?}", body[0]);
+        };
+        assert!(matches!(&**base, Expr { kind: ExprKind::Index(m, a), .. }
+            if matches!(&**m, Expr { kind: ExprKind::Identifier(n), .. } if n == "m") && matches!(&**a, Expr { kind: ExprKind::Identifier(n), .. } if n == "a")));
+        assert!(matches!(&**index, Expr { kind: ExprKind::Number(n), .. } if *n == BigUint::from(1u8)));
+        assert!(matches!(&**lhs, Expr { kind: ExprKind::Index(..), .. }));
+        assert!(matches!(&body[1], Statement { kind: StatementKind::VariableDecl(TypeName::Array(_, Some(2)), n, None), .. } if n == "ps"));
+        assert!(matches!(&body[2], Statement { kind: StatementKind::Assignment(Expr { kind: ExprKind::Index(..), .. }, Expr { kind: ExprKind::Identifier(_), .. }), .. }));
+    }
+
+    #[test]
+    fn test_parse_if_else() {
+        let functions = parse(
+            "function f(int a) returns (int) {
+                if (a > 0) { return 1; } else if (a < 0) return 0 - 1; else { a = 0; }
+                if (a > 1) a = 1;
+                return a;
+            }",
+        )
+        .unwrap();
+        let body = &functions[0].body;
+
+        let Statement { kind: StatementKind::If(Expr { kind: ExprKind::BinaryOp(_, BinaryOp::GreaterThan, _), .. }, then_branch, Some(else_branch)), .. } = &body[0] else {
+            panic!("unexpected statement: {:?}", body[0]);
+        };
+        assert!(matches!(&then_branch[..], [Statement { kind: StatementKind::Return(Some(Expr { kind: ExprKind::Number(n), .. })), .. }] if *n == BigUint::from(1u8)));
+        let [Statement { kind: StatementKind::If(_, nested_then, Some(nested_else)), .. }] = &else_branch[..] else {
+            panic!("unexpected else branch: {:?}", else_branch);
+        };
+        assert!(matches!(nested_then[..], [Statement { kind: StatementKind::Return(Some(_)), .. }]));
+        assert!(matches!(nested_else[..], [Statement { kind: StatementKind::Assignment(..), .. }]));
+        assert!(matches!(&body[1], Statement { kind: StatementKind::If(_, then_branch, None), .. } if then_branch.len() == 1));
+        assert!(matches!(body[2], Statement { kind: StatementKind::Return(Some(_)), .. }));
+    }
+
+    #[test]
+    fn test_parse_malformed_if() {
+        assert!(parse("function f() { if a > 0 { } }").is_err());
+        assert!(parse("function f() { if (a > 0 { } }").is_err());
+        assert!(parse("function f() { if (a > 0) { a = 1; }").is_err());
+        assert!(parse("function f() { else { } }").is_err());
+    }
+
+    #[test]
+    fn test_parse_loops() {
+        let functions = parse(
+            "function f(uint n) returns (uint) {
+                uint s = 0;
+                for (uint i = 0; i < n; i = i + 1) { if (i > 5) break; s = s + i; }
+                while (s > 100) s = s - 1;
+                do { s = s + 1; continue; } while (s < 10);
+                for (;;) { break; }
+                return s;
+            }",
+        )
+        .unwrap();
+        let body = &functions[0].body;
+
+        let Statement { kind: StatementKind::For(Some(init), Some(_), Some(update), loop_body), .. } = &body[1] else {
+            panic!("unexpected statement: {:?}", body[1]);
+        };
+        assert!(matches!(&**init, Statement { kind: StatementKind::VariableDecl(TypeName::Uint(256), n, Some(_)), .. } if n == "i"));
+        assert!(matches!(&**update, Statement { kind: StatementKind::Assignment(Expr { kind: ExprKind::Identifier(n), .. }, _), .. } if n == "i"));
+        assert!(matches!(&loop_body[0], Statement { kind: StatementKind::If(_, then_branch, None), .. } if matches!(then_branch[..], [Statement { kind: StatementKind::Break, .. }])));
+        assert!(matches!(&body[2], Statement { kind: StatementKind::While(Expr { kind: ExprKind::BinaryOp(..), .. }, loop_body), .. } if loop_body.len() == 1));
+        assert!(matches!(&body[3], Statement { kind: StatementKind::DoWhile(loop_body, _), .. } if matches!(loop_body[1], Statement { kind: StatementKind::Continue, .. })));
+        assert!(matches!(&body[4], Statement { kind: StatementKind::For(None, None, None, loop_body), .. } if matches!(loop_body[..], [Statement { kind: StatementKind::Break, .. }])));
+    }
+
+    #[test]
+    fn test_parse_malformed_loops() {
+        assert!(parse("function f() { while a > 0 { } }").is_err());
+        assert!(parse("function f() { do { } while (a > 0) }").is_err());
+        assert!(parse("function f() { do { } (a > 0); }").is_err());
+        assert!(parse("function f() { for (i = 0; i < 1; i = i + 1; ) { } }").is_err());
+        assert!(parse("function f() { for (return; ; ) { } }").is_err());
+        assert!(parse("function f() { break }").is_err());
+    }
+
+    #[test]
+    fn test_parse_nested_blocks() {
+        let functions = parse(
+            "function f() {
+                { uint a = 1; { } }
+                if (true) { { a = 2; } }
+            }",
+        )
+        .unwrap();
+        let body = &functions[0].body;
+
+        let Statement { kind: StatementKind::Block(block), .. } = &body[0] else {
+            panic!("unexpected statement: {:?}", body[0]);
+        };
+        assert!(matches!(&block[..], [
+            Statement { kind: StatementKind::VariableDecl(..), .. },
+            Statement { kind: StatementKind::Block(empty), .. },
+        ] if empty.is_empty()));
+        assert!(matches!(&body[1], Statement { kind: StatementKind::If(_, then_branch, None), .. }
+            if matches!(&then_branch[..], [Statement { kind: StatementKind::Block(inner), .. }] if inner.len() == 1)));
+        assert!(parse("function f() { { uint a = 1; }").is_err());
+    }
+
+    #[test]
+    fn test_parse_operator_precedence() {
+        let functions = parse("function f() { x = a || b && c == d + e * g < h - i / j; y = a - b - c; }").unwrap();
+        let Statement { kind: StatementKind::Assignment(_, expr), .. } = &functions[0].body[0] else {
+            panic!("unexpected statement: {:?}", functions[0].body[0]);
+        };
+        // a || (b && (c == ((d + (e * g)) < (h - (i / j)))))
+        let Expr { kind: ExprKind::BinaryOp(a, BinaryOp::LogicalOr, rest), .. } = expr else { panic!("{:?}", expr) };
+        assert!(matches!(&**a, Expr { kind: ExprKind::Identifier(n), .. } if n == "a"));
+        let Expr { kind: ExprKind::BinaryOp(_, BinaryOp::LogicalAnd, rest), .. } = &**rest else { panic!("{:?}", rest) };
+        let Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Equals, rest), .. } = &**rest else { panic!("{:?}", rest) };
+        let Expr { kind: ExprKind::BinaryOp(sum, BinaryOp::LessThan, difference), .. } = &**rest else { panic!("{:?}", rest) };
+        assert!(matches!(&**sum, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Add, product), .. }
+            if matches!(&**product, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Multiply, _), .. })));
+        assert!(matches!(&**difference, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Subtract, quotient), .. }
+            if matches!(&**quotient, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Divide, _), .. })));
+
+        // 同一优先级左结合：(a - b) - c
+        let Statement { kind: StatementKind::Assignment(_, expr), .. } = &functions[0].body[1] else { panic!() };
+        assert!(matches!(expr, Expr { kind: ExprKind::BinaryOp(lhs, BinaryOp::Subtract, c), .. }
+            if matches!(&**lhs, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Subtract, _), .. }) && matches!(&**c, Expr { kind: ExprKind::Identifier(n), .. } if n == "c")));
+    }
+
+    #[test]
+    fn test_parse_bitwise_operator_precedence() {
+        let functions = parse(
+            "function f() {
+                x = a | b ^ c & d << e + f % g;
+                y = a ** b ** c;
+                z = -a ** 2 * b;
+                w <<= 1;
+                v %= a >> b;
+            }",
+        )
+        .unwrap();
+        let body = &functions[0].body;
+
+        // a | (b ^ (c & (d << (e + (f % g)))))
+        let Statement { kind: StatementKind::Assignment(_, expr), .. } = &body[0] else { panic!("{:?}", body[0]) };
+        let Expr { kind: ExprKind::BinaryOp(_, BinaryOp::BitOr, rest), .. } = expr else { panic!("{:?}", expr) };
+        let Expr { kind: ExprKind::BinaryOp(_, BinaryOp::BitXor, rest), .. } = &**rest else { panic!("{:?}", rest) };
+        let Expr { kind: ExprKind::BinaryOp(_, BinaryOp::BitAnd, rest), .. } = &**rest else { panic!("{:?}", rest) };
+        let Expr { kind: ExprKind::BinaryOp(_, BinaryOp::ShiftLeft, rest), .. } = &**rest else { panic!("{:?}", rest) };
+        assert!(matches!(&**rest, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Add, remainder), .. }
+            if matches!(&**remainder, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Modulo, _), .. })));
+
+        // ** 右结合：a ** (b ** c)
+        let Statement { kind: StatementKind::Assignment(_, expr), .. } = &body[1] else { panic!("{:?}", body[1]) };
+        assert!(matches!(expr, Expr { kind: ExprKind::BinaryOp(a, BinaryOp::Power, rest), .. }
+            if matches!(&**a, Expr { kind: ExprKind::Identifier(n), .. } if n == "a") && matches!(&**rest, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Power, _), .. })));
+
+        // 前缀运算符比 ** 结合得更紧密，** 比乘法更紧密：((-a) ** 2) * b
+        let Statement { kind: StatementKind::Assignment(_, expr), .. } = &body[2] else { panic!("{:?}", body[2]) };
+        assert!(matches!(expr, Expr { kind: ExprKind::BinaryOp(power, BinaryOp::Multiply, _), .. }
+            if matches!(&**power, Expr { kind: ExprKind::BinaryOp(base, BinaryOp::Power, _), .. } if matches!(&**base, Expr { kind: ExprKind::Prefix(UnaryOp::Negate, _), .. }))));
+
+        assert!(matches!(&body[3], Statement { kind: StatementKind::CompoundAssignment(_, BinaryOp::ShiftLeft, Expr { kind: ExprKind::Number(n), .. }), .. } if *n == BigUint::from(1u8)));
+        assert!(matches!(&body[4], Statement { kind: StatementKind::CompoundAssignment(_, BinaryOp::Modulo, Expr { kind: ExprKind::BinaryOp(_, BinaryOp::ShiftRight, _), .. }), .. }));
+    }
+
+    #[test]
+    fn test_parse_calls() {
+        let functions = parse("function f() { g(); x = h(a, b + 1) * 2; m[k(1)] = g()[0]; }").unwrap();
+        let body = &functions[0].body;
+
+        assert!(matches!(&body[0], Statement { kind: StatementKind::Expression(Expr { kind: ExprKind::Call(name, args), .. }), .. } if name == "g" && args.is_empty()));
+        let Statement { kind: StatementKind::Assignment(_, Expr { kind: ExprKind::BinaryOp(call, BinaryOp::Multiply, _), .. }), .. } = &body[1] else {
+            panic!("unexpected statement: {:?}", body[1]);
+        };
+        assert!(matches!(&**call, Expr { kind: ExprKind::Call(name, args), .. } if name == "h" && args.len() == 2
+            && matches!(args[1], Expr { kind: ExprKind::BinaryOp(_, BinaryOp::Add, _), .. })));
+        assert!(matches!(&body[2], Statement { kind: StatementKind::Assignment(Expr { kind: ExprKind::Index(_, key), .. }, Expr { kind: ExprKind::Index(base, _), .. }), .. }
+            if matches!(&**key, Expr { kind: ExprKind::Call(..), .. }) && matches!(&**base, Expr { kind: ExprKind::Call(..), .. })));
+
+        assert!(parse("function f() { g(a b); }").is_err());
+        assert!(parse("function f() { g(a,); }").is_err());
+    }
+
+    #[test]
+    fn test_parse_unary_and_compound_assignment() {
+        let functions = parse(
+            "function f() {
+                x = -a * !b + ~c;
+                m[k]++;
+                --y;
+                z += a - b;
+                for (i = 0; i < n; i++) { s /= 2; }
+                w = a++ + ++b;
+            }",
+        )
+        .unwrap();
+        let body = &functions[0].body;
+
+        // 前缀运算符比乘法结合得更紧密：((-a) * (!b)) + (~c)
+        let Statement { kind: StatementKind::Assignment(_, Expr { kind: ExprKind::BinaryOp(product, BinaryOp::Add, bit_not), .. }), .. } = &body[0] else {
+            panic!("unexpected statement: {:?}", body[0]);
+        };
+        assert!(matches!(&**product, Expr { kind: ExprKind::BinaryOp(neg, BinaryOp::Multiply, not), .. }
+            if matches!(&**neg, Expr { kind: ExprKind::Prefix(UnaryOp::Negate, _), .. }) && matches!(&**not, Expr { kind: ExprKind::Prefix(UnaryOp::Not, _), .. })));
+        assert!(matches!(&**bit_not, Expr { kind: ExprKind::Prefix(UnaryOp::BitNot, _), .. }));
+
+        assert!(matches!(&body[1], Statement { kind: StatementKind::Expression(Expr { kind: ExprKind::Postfix(UnaryOp::Increment, target), .. }), .. }
+            if matches!(&**target, Expr { kind: ExprKind::Index(..), .. })));
+        assert!(matches!(&body[2], Statement { kind: StatementKind::Expression(Expr { kind: ExprKind::Prefix(UnaryOp::Decrement, _), .. }), .. }));
+        assert!(matches!(&body[3], Statement { kind: StatementKind::CompoundAssignment(Expr { kind: ExprKind::Identifier(n), .. }, BinaryOp::Add, Expr { kind: ExprKind::BinaryOp(..), .. }), .. }
+            if n == "z"));
+        let Statement { kind: StatementKind::For(_, _, Some(update), loop_body), .. } = &body[4] else {
+            panic!("unexpected statement: {:?}", body[4]);
+        };
+        assert!(matches!(&**update, Statement { kind: StatementKind::Expression(Expr { kind: ExprKind::Postfix(UnaryOp::Increment, _), .. }), .. }));
+        assert!(matches!(&loop_body[0], Statement { kind: StatementKind::CompoundAssignment(_, BinaryOp::Divide, Expr { kind: ExprKind::Number(n), .. }), .. } if *n == BigUint::from(2u8)));
+        assert!(matches!(&body[5], Statement { kind: StatementKind::Assignment(_, Expr { kind: ExprKind::BinaryOp(lhs, BinaryOp::Add, rhs), .. }), .. }
+            if matches!(&**lhs, Expr { kind: ExprKind::Postfix(..), .. }) && matches!(&**rhs, Expr { kind: ExprKind::Prefix(UnaryOp::Increment, _), .. })));
+    }
+
+    #[test]
+    fn test_parse_malformed_types() {
+        assert!(parse("function f(mapping(uint bool) m) { }").is_err());
+        assert!(parse("function f(uint[x] a) { }").is_err());
+    }
+
+    #[test]
+    fn test_parse_contract() {
+        let unit = parse_unit(
+            "contract Counter {
+                uint public count = 1;
+                mapping(address => uint) balances;
+                constructor(uint start) public { count = start; }
+                function get() public view returns (uint) { return count; }
+            }
+            function helper() pure { }",
+        )
+        .unwrap();
+
+        assert_eq!(unit.contracts.len(), 1);
+        let contract = &unit.contracts[0];
+        assert_eq!(contract.name, "Counter");
+        assert_eq!(contract.state_variables.len(), 2);
+        assert_eq!(contract.state_variables[0].var_type, TypeName::Uint(256));
+        assert_eq!(contract.state_variables[0].name, "count");
+        assert!(matches!(&contract.state_variables[0].initializer, Some(Expr { kind: ExprKind::Number(n), .. }) if *n == BigUint::from(1u8)));
+        assert_eq!(contract.state_variables[1].var_type.to_string(), "mapping(address => uint256)");
+        assert!(contract.state_variables[1].initializer.is_none());
+
+        let constructor = contract.constructor.as_ref().unwrap();
+        assert_eq!(constructor.params, vec![(TypeName::Uint(256), "start".to_string())]);
+        assert_eq!(contract.functions.len(), 1);
+        assert_eq!(contract.functions[0].returns, vec![TypeName::Uint(256)]);
+
+        let names: Vec<_> = unit.all_functions().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["helper", "constructor", "get"]);
+    }
+
+    #[test]
+    fn test_parse_malformed_contract() {
+        assert!(parse_unit("contract { }").is_err());
+        assert!(parse_unit("contract C { uint x }").is_err());
+        assert!(parse_unit("contract C { function f() { }").is_err());
+        assert!(parse_unit("contract C { constructor() { } constructor() { } }").is_err());
+    }
+
+    #[test]
+    fn test_parse_spans() {
+        let source = "function f() {\n    x = (a + 1) * b;\n    return x;\n}";
+        let functions = parse(source).unwrap();
+        let func = &functions[0];
+        assert_eq!((func.span.start, func.span.end, func.span.line, func.span.column), (0, source.len(), 1, 1));
+
+        // 语句包括结尾的分号
+        let stmt = &func.body[0];
+        assert_eq!(&source[stmt.span.start..stmt.span.end], "x = (a + 1) * b;");
+        assert_eq!((stmt.span.line, stmt.span.column), (2, 5));
+
+        let StatementKind::Assignment(_, Expr { kind: ExprKind::BinaryOp(lhs, _, rhs), span }) = &stmt.kind else {
+            panic!("{:?}", stmt)
+        };
+        assert_eq!(&source[span.start..span.end], "(a + 1) * b");
+        assert_eq!((span.line, span.column), (2, 9));
+        // 括号中的表达式的位置包括括号
+        assert_eq!(&source[lhs.span.start..lhs.span.end], "(a + 1)");
+        assert_eq!((rhs.span.line, rhs.span.column), (2, 19));
+        assert_eq!((func.body[1].span.line, func.body[1].span.column), (3, 5));
+    }
+
+    #[test]
+    fn test_parse_error_locations() {
+        let source = "function f() {\n    x = a b;\n}";
+        let error = parse(source).unwrap_err().remove(0);
+        assert!(matches!(error, ParseError::UnexpectedToken(_)));
+        assert_eq!(error.to_string(), "Expected ';' after expression");
+        let error = error.diagnostic();
+        let span = error.span().unwrap();
+        assert_eq!((span.line, span.column, &source[span.start..span.end]), (2, 11, "b"));
+
+        // 未闭合的代码块指向输入末尾，并标出左大括号
+        let error = Diagnostic::from(parse("function f() {\n    x = 1;").unwrap_err().remove(0));
+        assert_eq!(error.code, codes::UNCLOSED_DELIMITER);
+        assert_eq!(error.labels[0].span.column, 11);
+        assert_eq!((error.labels[1].span.line, error.labels[1].span.column), (1, 14));
+        assert!(!error.labels[1].primary);
+
+        let error = Diagnostic::from(
+            parse_unit("contract C { constructor() { } constructor() { } }")
+                .unwrap_err()
+                .remove(0),
+        );
+        assert_eq!(error.code, codes::DUPLICATE_DECLARATION);
+        assert_eq!(error.span().unwrap().column, 32);
+        assert_eq!(error.labels[1].span.column, 14);
+    }
+
+    #[test]
+    fn test_parse_reports_all_errors() {
+        let source = "function f() {
+                x = ;
+                if (a { y = 1; }
+                z = 2;
+            }
+            uint stray;
+            function g() returns (uint) { return 1 }
+            contract C {
+                uint a
+                function h() { }
+                constructor() { } constructor() { }
+            }";
+        let errors = parse_unit(source).unwrap_err();
+        let lines: Vec<_> = errors
+            .iter()
+            .map(|e| (e.to_string(), e.diagnostic().span().unwrap().line))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("Expected expression".to_string(), 2),
+                ("Expected ')' after if condition".to_string(), 3),
+                ("Expected contract or function definition".to_string(), 6),
+                ("Expected ';' after return expression".to_string(), 7),
+                ("Expected '=' or ';' after variable name".to_string(), 10),
+                ("Contract C has more than one constructor".to_string(), 11),
+            ]
+        );
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_parse_recovers_unclosed_blocks() {
+        let errors = parse("function f() { if (a) { x = 1; } function g() { y = ; }").unwrap_err();
+        let codes: Vec<_> = errors.iter().map(|e| e.diagnostic().code).collect();
+        assert_eq!(codes, vec![codes::UNCLOSED_DELIMITER, codes::UNEXPECTED_TOKEN]);
+        assert_eq!(errors[0].diagnostic().span().unwrap().column, 34);
+
+        // 出错的语句之后的语句照常解析
+        let mut parser = Parser::new(Lexer::new("function f() { x = ; y = 1; }").collect());
+        assert!(parser.parse().is_err());
+        let mut parser = Parser::new(Lexer::new("contract C { uint x = ; uint y; }").collect());
+        assert_eq!(parser.parse().unwrap_err().len(), 1);
+    }
+
+    #[test]
+    fn test_number_literals_beyond_64_bits() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        let functions = parse(&format!("function f() {{ x = {}; }}", max)).unwrap();
+        assert!(matches!(&functions[0].body[..], [Statement { kind: StatementKind::Assignment(_, Expr { kind: ExprKind::Number(n), .. }), .. }]
+            if n.to_string() == max && n.bits() == 256));
+    }
+}
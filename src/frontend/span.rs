@@ -0,0 +1,98 @@
+/// 源码中的一段区间。`start` 和 `end` 是字节偏移（左闭右开），`line` 和 `column`
+/// 是起点所在的行号和列号，都从 1 开始，列号按字符计数。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// 从 `self` 的起点到 `other` 的终点的区间。
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            end: self.end.max(other.end),
+            ..self
+        }
+    }
+}
+
+/// 字节偏移到行列号的映射，记录每一行起点的偏移。
+pub struct LineIndex<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { source, line_starts }
+    }
+
+    /// 偏移 `offset` 处的行号和列号，都从 1 开始。
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self
+            .source
+            .get(line_start..offset)
+            .map_or(offset - line_start, |text| text.chars().count());
+        (line, column + 1)
+    }
+
+    /// 第 `line` 行的内容，不含换行符，行号从 1 开始。
+    pub fn line_text(&self, line: usize) -> &'a str {
+        let Some(&start) = self.line_starts.get(line.wrapping_sub(1)) else {
+            return "";
+        };
+        let end = self.line_starts.get(line).map_or(self.source.len(), |&next| next - 1);
+        self.source[start..end].trim_end_matches('\r')
+    }
+
+    /// 字节区间 `[start, end)` 对应的 [`Span`]。
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let (line, column) = self.line_col(start);
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col() {
+        let index = LineIndex::new("ab\n\nc变d\n");
+        assert_eq!(index.line_col(0), (1, 1));
+        assert_eq!(index.line_col(2), (1, 3));
+        assert_eq!(index.line_col(3), (2, 1));
+        assert_eq!(index.line_col(4), (3, 1));
+        // 列号按字符计数，多字节字符只占一列
+        assert_eq!(index.line_col(8), (3, 3));
+        assert_eq!(index.line_col(10), (4, 1));
+    }
+
+    #[test]
+    fn test_line_text() {
+        let index = LineIndex::new("ab\r\n\nc");
+        assert_eq!(index.line_text(1), "ab");
+        assert_eq!(index.line_text(2), "");
+        assert_eq!(index.line_text(3), "c");
+        assert_eq!(index.line_text(4), "");
+    }
+
+    #[test]
+    fn test_span_to() {
+        let index = LineIndex::new("a +\n  b");
+        let span = index.span(0, 1).to(index.span(6, 7));
+        assert_eq!(span, Span { start: 0, end: 7, line: 1, column: 1 });
+    }
+}
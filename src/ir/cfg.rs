@@ -0,0 +1,252 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::IRFunction;
+
+/// 函数的控制流图，基本块以其在 `IRFunction::blocks` 中的下标表示，入口块为 0。
+#[derive(Debug, Clone)]
+pub struct Cfg {
+    pub succs: Vec<Vec<usize>>,
+    pub preds: Vec<Vec<usize>>,
+}
+
+impl Cfg {
+    pub fn new(func: &IRFunction) -> Self {
+        let index: HashMap<&str, usize> = func
+            .blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| (block.label.as_str(), i))
+            .collect();
+
+        let mut succs = vec![Vec::new(); func.blocks.len()];
+        let mut preds = vec![Vec::new(); func.blocks.len()];
+        for (i, block) in func.blocks.iter().enumerate() {
+            for label in block.terminator.successors() {
+                if let Some(&succ) = index.get(label.as_str()) {
+                    if !succs[i].contains(&succ) {
+                        succs[i].push(succ);
+                        preds[succ].push(i);
+                    }
+                }
+            }
+        }
+
+        Self { succs, preds }
+    }
+
+    /// 从入口块出发的逆后序，不可达的块不会出现在结果中。
+    pub fn reverse_postorder(&self) -> Vec<usize> {
+        let mut order = Vec::new();
+        if self.succs.is_empty() {
+            return order;
+        }
+
+        let mut visited = vec![false; self.succs.len()];
+        // 显式栈：(块, 下一个要访问的后继下标)
+        let mut stack = vec![(0, 0)];
+        visited[0] = true;
+        while let Some((block, next)) = stack.pop() {
+            if let Some(&succ) = self.succs[block].get(next) {
+                stack.push((block, next + 1));
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            } else {
+                order.push(block);
+            }
+        }
+
+        order.reverse();
+        order
+    }
+}
+
+/// 删除从入口不可达的基本块。
+pub fn remove_unreachable_blocks(func: &mut IRFunction) {
+    let reachable: HashSet<usize> = Cfg::new(func).reverse_postorder().into_iter().collect();
+    let mut index = 0;
+    func.blocks.retain(|_| {
+        let keep = reachable.contains(&index);
+        index += 1;
+        keep
+    });
+}
+
+/// 支配树与支配边界，使用 Cooper、Harvey 和 Kennedy 的迭代算法计算。
+#[derive(Debug, Clone)]
+pub struct Dominators {
+    idom: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    frontiers: Vec<Vec<usize>>,
+}
+
+impl Dominators {
+    pub fn new(cfg: &Cfg) -> Self {
+        let count = cfg.succs.len();
+        let rpo = cfg.reverse_postorder();
+        let mut rpo_index = vec![usize::MAX; count];
+        for (i, &block) in rpo.iter().enumerate() {
+            rpo_index[block] = i;
+        }
+
+        let mut idom: Vec<Option<usize>> = vec![None; count];
+        if let Some(&entry) = rpo.first() {
+            idom[entry] = Some(entry);
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in rpo.iter().skip(1) {
+                let mut new_idom = None;
+                for &pred in &cfg.preds[block] {
+                    if idom[pred].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(current) => intersect(&idom, &rpo_index, pred, current),
+                    });
+                }
+                if new_idom.is_some() && idom[block] != new_idom {
+                    idom[block] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut children = vec![Vec::new(); count];
+        for &block in rpo.iter().skip(1) {
+            if let Some(parent) = idom[block] {
+                children[parent].push(block);
+            }
+        }
+
+        let mut frontiers: Vec<Vec<usize>> = vec![Vec::new(); count];
+        for &block in &rpo {
+            if cfg.preds[block].len() < 2 {
+                continue;
+            }
+            for &pred in &cfg.preds[block] {
+                let mut runner = pred;
+                while idom[runner].is_some() && Some(runner) != idom[block] {
+                    if !frontiers[runner].contains(&block) {
+                        frontiers[runner].push(block);
+                    }
+                    match idom[runner] {
+                        Some(next) if next != runner => runner = next,
+                        _ => break,
+                    }
+                }
+            }
+        }
+
+        // 入口块的直接支配者对外表现为不存在
+        if let Some(&entry) = rpo.first() {
+            idom[entry] = None;
+        }
+
+        Self {
+            idom,
+            children,
+            frontiers,
+        }
+    }
+
+    /// 直接支配者；入口块和不可达块返回 `None`。
+    pub fn idom(&self, block: usize) -> Option<usize> {
+        self.idom[block]
+    }
+
+    /// 支配树中的子节点。
+    pub fn children(&self, block: usize) -> &[usize] {
+        &self.children[block]
+    }
+
+    /// 支配边界。
+    pub fn frontier(&self, block: usize) -> &[usize] {
+        &self.frontiers[block]
+    }
+
+    /// `a` 是否支配 `b`（每个块都支配自身）。
+    pub fn dominates(&self, a: usize, b: usize) -> bool {
+        let mut current = b;
+        loop {
+            if current == a {
+                return true;
+            }
+            match self.idom[current] {
+                Some(parent) => current = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+fn intersect(idom: &[Option<usize>], rpo_index: &[usize], a: usize, b: usize) -> usize {
+    let (mut finger1, mut finger2) = (a, b);
+    while finger1 != finger2 {
+        while rpo_index[finger1] > rpo_index[finger2] {
+            finger1 = idom[finger1].unwrap_or(finger2);
+        }
+        while rpo_index[finger2] > rpo_index[finger1] {
+            finger2 = idom[finger2].unwrap_or(finger1);
+        }
+    }
+    finger1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BasicBlock, Terminator, Value};
+
+    fn block(label: &str, terminator: Terminator) -> BasicBlock {
+        BasicBlock {
+            label: label.to_string(),
+            instructions: Vec::new(),
+            terminator,
+        }
+    }
+
+    /// entry -> (then | else) -> merge，以及一个不可达块
+    fn diamond() -> IRFunction {
+        let mut func = IRFunction::new("f".to_string());
+        let cond = Value::Local("c".to_string());
+        func.add_block(block(
+            "entry",
+            Terminator::Branch(cond, "then".to_string(), "else".to_string()),
+        ));
+        func.add_block(block("then", Terminator::Jump("merge".to_string())));
+        func.add_block(block("else", Terminator::Jump("merge".to_string())));
+        func.add_block(block("merge", Terminator::Return(Vec::new())));
+        func.add_block(block("dead", Terminator::Jump("merge".to_string())));
+        func
+    }
+
+    #[test]
+    fn test_diamond_dominators() {
+        let func = diamond();
+        let cfg = Cfg::new(&func);
+        let doms = Dominators::new(&cfg);
+
+        assert_eq!(cfg.reverse_postorder().len(), 4);
+        assert_eq!(doms.idom(0), None);
+        assert_eq!(doms.idom(1), Some(0));
+        assert_eq!(doms.idom(3), Some(0));
+        assert!(doms.dominates(0, 3));
+        assert!(!doms.dominates(1, 3));
+        assert_eq!(doms.frontier(1), &[3]);
+        assert_eq!(doms.frontier(2), &[3]);
+        assert!(doms.frontier(0).is_empty());
+    }
+
+    #[test]
+    fn test_remove_unreachable_blocks() {
+        let mut func = diamond();
+        remove_unreachable_blocks(&mut func);
+        let labels: Vec<_> = func.blocks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["entry", "then", "else", "merge"]);
+    }
+}
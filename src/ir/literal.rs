@@ -0,0 +1,259 @@
+use num_bigint::{BigInt, Sign};
+use num_rational::BigRational;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::frontend::diagnostic::{codes, Diagnostic};
+use crate::frontend::parser::{BinaryOp, Expr, ExprKind, UnaryOp};
+use crate::ir::Constant;
+
+/// 字面量表达式求值过程中分子、分母的最大位数，与 solc 对有理数常量的限制相同。
+const MAX_BITS: u64 = 4096;
+
+/// 是否是只由数字字面量和算术、位运算组成的表达式。
+pub fn is_literal(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Number(_) => true,
+        ExprKind::Prefix(UnaryOp::Negate | UnaryOp::BitNot, operand) => is_literal(operand),
+        ExprKind::BinaryOp(lhs, op, rhs) => is_arithmetic(op) && is_literal(lhs) && is_literal(rhs),
+        _ => false,
+    }
+}
+
+fn is_arithmetic(op: &BinaryOp) -> bool {
+    !is_comparison(op) && !matches!(op, BinaryOp::LogicalAnd | BinaryOp::LogicalOr)
+}
+
+fn is_comparison(op: &BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Equals
+            | BinaryOp::NotEquals
+            | BinaryOp::GreaterThan
+            | BinaryOp::LessThan
+            | BinaryOp::GreaterThanOrEqual
+            | BinaryOp::LessThanOrEqual
+    )
+}
+
+/// 在编译期求出字面量表达式的值，表达式含有变量等其他成分时返回 `None`。
+///
+/// 与 Solidity 相同，字面量表达式按任意精度的有理数计算，中间结果不会溢出或截断，
+/// 如 `2**256 - 1` 和 `5 / 2 * 2`；只有最终结果必须是整数，能否用目标类型表示
+/// 在使用它的地方检查。比较两个字面量表达式得到 bool 常量。
+pub fn fold(expr: &Expr) -> Option<Result<Constant, Diagnostic>> {
+    match &expr.kind {
+        ExprKind::BinaryOp(lhs, op, rhs) if is_comparison(op) && is_literal(lhs) && is_literal(rhs) => {
+            Some(compare(lhs, op, rhs))
+        }
+        _ if is_literal(expr) => Some(evaluate(expr).and_then(|value| {
+            if value.is_integer() {
+                Ok(Constant::Int(value.to_integer()))
+            } else {
+                Err(Diagnostic::error(
+                    codes::TYPE_MISMATCH,
+                    format!("Literal expression evaluates to {}, which is not an integer", value),
+                )
+                .with_label(expr.span, "not an integer"))
+            }
+        })),
+        _ => None,
+    }
+}
+
+fn compare(lhs: &Expr, op: &BinaryOp, rhs: &Expr) -> Result<Constant, Diagnostic> {
+    let (lhs, rhs) = (evaluate(lhs)?, evaluate(rhs)?);
+    Ok(Constant::Bool(match op {
+        BinaryOp::Equals => lhs == rhs,
+        BinaryOp::NotEquals => lhs != rhs,
+        BinaryOp::GreaterThan => lhs > rhs,
+        BinaryOp::LessThan => lhs < rhs,
+        BinaryOp::GreaterThanOrEqual => lhs >= rhs,
+        _ => lhs <= rhs,
+    }))
+}
+
+/// 求出字面量表达式的有理数值。错误指向出错的最内层子表达式。
+fn evaluate(expr: &Expr) -> Result<BigRational, Diagnostic> {
+    let value = match &expr.kind {
+        ExprKind::Number(n) => Ok(BigRational::from_integer(BigInt::from(n.clone()))),
+        ExprKind::Prefix(UnaryOp::Negate, operand) => evaluate(operand).map(|value| -value),
+        // 按无限位宽的补码取反，即 -x - 1
+        ExprKind::Prefix(UnaryOp::BitNot, operand) => {
+            integer(operand, "~").map(|value| BigRational::from_integer(!value))
+        }
+        ExprKind::BinaryOp(lhs, op, rhs) if is_arithmetic(op) => binary(lhs, op, rhs),
+        _ => Err(Diagnostic::error(codes::UNSUPPORTED, "Expression is not a literal expression")),
+    };
+    value.and_then(|value| check_size(value, expr)).map_err(|e| e.or_label(expr.span))
+}
+
+/// 位运算和移位只能作用于整数。
+fn integer(expr: &Expr, op: &str) -> Result<BigInt, Diagnostic> {
+    let value = evaluate(expr)?;
+    if value.is_integer() {
+        Ok(value.to_integer())
+    } else {
+        Err(Diagnostic::error(
+            codes::TYPE_MISMATCH,
+            format!("Operator {} requires integer operands, found {}", op, value),
+        )
+        .with_label(expr.span, "not an integer"))
+    }
+}
+
+fn binary(lhs: &Expr, op: &BinaryOp, rhs: &Expr) -> Result<BigRational, Diagnostic> {
+    let symbol = match op {
+        BinaryOp::BitAnd => "&",
+        BinaryOp::BitOr => "|",
+        BinaryOp::BitXor => "^",
+        BinaryOp::ShiftLeft => "<<",
+        BinaryOp::ShiftRight => ">>",
+        _ => {
+            let (l, r) = (evaluate(lhs)?, evaluate(rhs)?);
+            return match op {
+                BinaryOp::Add => Ok(l + r),
+                BinaryOp::Subtract => Ok(l - r),
+                BinaryOp::Multiply => Ok(l * r),
+                BinaryOp::Divide | BinaryOp::Modulo if r.is_zero() => Err(Diagnostic::error(
+                    codes::CONSTANT_EVALUATION,
+                    "Division by zero in literal expression",
+                )
+                .with_label(rhs.span, "this evaluates to zero")),
+                BinaryOp::Divide => Ok(l / r),
+                // 商向零取整，余数的符号与被除数相同
+                BinaryOp::Modulo => Ok(&l - &r * (&l / &r).trunc()),
+                _ => power(l, r, rhs),
+            };
+        }
+    };
+
+    let (l, r) = (integer(lhs, symbol)?, integer(rhs, symbol)?);
+    let value = match op {
+        BinaryOp::BitAnd => l & r,
+        BinaryOp::BitOr => l | r,
+        BinaryOp::BitXor => l ^ r,
+        _ if r.is_negative() => {
+            return Err(Diagnostic::error(
+                codes::TYPE_MISMATCH,
+                format!("Right operand of {:?} must be an unsigned integer", op),
+            )
+            .with_label(rhs.span, "negative shift amount"))
+        }
+        // 左移 n 位即乘以 2**n，右移向负无穷取整
+        BinaryOp::ShiftLeft if l.is_zero() => l,
+        BinaryOp::ShiftLeft => match r.to_u64().filter(|amount| *amount <= MAX_BITS) {
+            Some(amount) => l << amount,
+            None => return Err(too_large()),
+        },
+        _ => match r.to_u64().filter(|amount| *amount <= l.bits()) {
+            Some(amount) => l >> amount,
+            None if l.is_negative() => BigInt::from(-1),
+            None => BigInt::zero(),
+        },
+    };
+    Ok(BigRational::from_integer(value))
+}
+
+/// `base ** exponent`。指数必须是整数，为负数时结果是底数倒数的乘方。
+fn power(base: BigRational, exponent: BigRational, rhs: &Expr) -> Result<BigRational, Diagnostic> {
+    if !exponent.is_integer() {
+        return Err(Diagnostic::error(
+            codes::TYPE_MISMATCH,
+            format!("Exponent must be an integer, found {}", exponent),
+        )
+        .with_label(rhs.span, "not an integer"));
+    }
+    let exponent = exponent.to_integer();
+    if base.is_zero() || base.abs().is_one() {
+        let odd = exponent.bit(0);
+        return match (base.is_zero(), exponent.sign()) {
+            (true, Sign::Minus) => Err(Diagnostic::error(
+                codes::CONSTANT_EVALUATION,
+                "Division by zero in literal expression",
+            )
+            .with_label(rhs.span, "negative power of zero")),
+            (true, Sign::NoSign) => Ok(BigRational::one()),
+            (true, Sign::Plus) => Ok(base),
+            _ if base.is_negative() && !odd => Ok(BigRational::one()),
+            _ => Ok(base),
+        };
+    }
+
+    // 底数的绝对值不是 0 或 1 时，结果至少有 (位数 - 1) * 指数 位
+    let bits = base.numer().bits().max(base.denom().bits()) - 1;
+    let magnitude = exponent
+        .abs()
+        .to_u64()
+        .filter(|exponent| exponent.saturating_mul(bits) <= MAX_BITS)
+        .ok_or_else(too_large)?;
+    let result = base.pow(magnitude as i32);
+    Ok(if exponent.is_negative() { result.recip() } else { result })
+}
+
+fn check_size(value: BigRational, expr: &Expr) -> Result<BigRational, Diagnostic> {
+    if value.numer().bits() > MAX_BITS || value.denom().bits() > MAX_BITS {
+        return Err(too_large().with_label(expr.span, ""));
+    }
+    Ok(value)
+}
+
+fn too_large() -> Diagnostic {
+    Diagnostic::error(
+        codes::CONSTANT_EVALUATION,
+        format!("Literal expression exceeds {} bits", MAX_BITS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::lexer::Lexer;
+    use crate::frontend::parser::{Parser, StatementKind};
+
+    /// 解析 `return <expr>;` 中的表达式并求值。
+    fn fold_source(expr: &str) -> Option<Result<Constant, Diagnostic>> {
+        let source = format!("function f() {{ return {}; }}", expr);
+        let tokens: Vec<_> = Lexer::new(&source).collect();
+        let unit = Parser::new(tokens).parse().unwrap();
+        let StatementKind::Return(Some(expr)) = &unit.functions[0].body[0].kind else {
+            panic!("expected a return statement");
+        };
+        fold(expr)
+    }
+
+    fn int(expr: &str) -> BigInt {
+        match fold_source(expr) {
+            Some(Ok(Constant::Int(n))) => n,
+            other => panic!("{} did not fold to an integer: {:?}", expr, other),
+        }
+    }
+
+    #[test]
+    fn test_fold_rational_arithmetic() {
+        assert_eq!(int("1 / 3 * 3"), BigInt::from(1));
+        assert_eq!(int("-7 % 3"), BigInt::from(-1));
+        assert_eq!(int("7 % -3"), BigInt::from(1));
+        assert_eq!(int("-5 >> 1"), BigInt::from(-3));
+        assert_eq!(int("~0"), BigInt::from(-1));
+        assert_eq!(int("4 ** -1 * 8"), BigInt::from(2));
+        assert_eq!(int("(-1) ** 255"), BigInt::from(-1));
+        assert_eq!(int("1 << 256 >> 300"), BigInt::from(0));
+        assert!(matches!(fold_source("1 / 2 < 1"), Some(Ok(Constant::Bool(true)))));
+        // 含有变量的表达式不在编译期求值
+        assert!(fold_source("x + 1").is_none());
+    }
+
+    #[test]
+    fn test_fold_errors() {
+        let message = |expr: &str| match fold_source(expr) {
+            Some(Err(diagnostic)) => diagnostic.message,
+            other => panic!("{} should not fold: {:?}", expr, other),
+        };
+        assert!(message("1 / 0").contains("Division by zero"));
+        assert!(message("0 ** -1").contains("Division by zero"));
+        assert!(message("2 ** (1 / 2)").contains("Exponent must be an integer"));
+        assert!(message("1 << -1").contains("must be an unsigned integer"));
+        assert!(message("1 << 5000").contains("exceeds 4096 bits"));
+        assert!(message("3 / 2").contains("not an integer"));
+    }
+}
@@ -0,0 +1,1870 @@
+use std::collections::HashMap;
+
+use num_bigint::Sign;
+use thiserror::Error;
+
+use crate::frontend::diagnostic::{codes, Diagnostic};
+use crate::frontend::parser::{
+    BinaryOp, Contract, Expr, ExprKind, Function, StateVariable, Statement, StatementKind, TypeName, UnaryOp,
+};
+use crate::frontend::span::Span;
+use crate::ir::literal;
+use crate::ir::storage::StorageLayout;
+use crate::ir::{BasicBlock, Constant, IRFunction, IRType, Instruction, Terminator, Value};
+
+/// 语义错误。按种类区分，各自带有指向源码的诊断信息。
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum SemaError {
+    #[error("{}", .0.message)]
+    DuplicateDeclaration(Diagnostic),
+    #[error("{}", .0.message)]
+    UndeclaredIdentifier(Diagnostic),
+    #[error("{}", .0.message)]
+    TypeMismatch(Diagnostic),
+    #[error("{}", .0.message)]
+    WrongArgumentCount(Diagnostic),
+    #[error("{}", .0.message)]
+    InvalidControlFlow(Diagnostic),
+    #[error("{}", .0.message)]
+    ConstantEvaluation(Diagnostic),
+    #[error("{}", .0.message)]
+    Unsupported(Diagnostic),
+}
+
+impl SemaError {
+    pub fn diagnostic(&self) -> &Diagnostic {
+        match self {
+            SemaError::DuplicateDeclaration(diagnostic)
+            | SemaError::UndeclaredIdentifier(diagnostic)
+            | SemaError::TypeMismatch(diagnostic)
+            | SemaError::WrongArgumentCount(diagnostic)
+            | SemaError::InvalidControlFlow(diagnostic)
+            | SemaError::ConstantEvaluation(diagnostic)
+            | SemaError::Unsupported(diagnostic) => diagnostic,
+        }
+    }
+}
+
+impl From<Diagnostic> for SemaError {
+    /// 按错误码归类。
+    fn from(diagnostic: Diagnostic) -> Self {
+        match diagnostic.code {
+            codes::DUPLICATE_DECLARATION => SemaError::DuplicateDeclaration(diagnostic),
+            codes::UNDECLARED_IDENTIFIER => SemaError::UndeclaredIdentifier(diagnostic),
+            codes::TYPE_MISMATCH => SemaError::TypeMismatch(diagnostic),
+            codes::WRONG_ARGUMENT_COUNT => SemaError::WrongArgumentCount(diagnostic),
+            codes::INVALID_CONTROL_FLOW => SemaError::InvalidControlFlow(diagnostic),
+            codes::CONSTANT_EVALUATION => SemaError::ConstantEvaluation(diagnostic),
+            _ => SemaError::Unsupported(diagnostic),
+        }
+    }
+}
+
+impl From<SemaError> for Diagnostic {
+    fn from(error: SemaError) -> Self {
+        match error {
+            SemaError::DuplicateDeclaration(diagnostic)
+            | SemaError::UndeclaredIdentifier(diagnostic)
+            | SemaError::TypeMismatch(diagnostic)
+            | SemaError::WrongArgumentCount(diagnostic)
+            | SemaError::InvalidControlFlow(diagnostic)
+            | SemaError::ConstantEvaluation(diagnostic)
+            | SemaError::Unsupported(diagnostic) => diagnostic,
+        }
+    }
+}
+
+/// 将 Solidity 类型转换为 IR 类型。
+pub fn convert_solidity_type_to_ir_type(solidity_type: &TypeName) -> Result<IRType, String> {
+    match solidity_type {
+        TypeName::Uint(bits) => Ok(IRType::integer(*bits, false)),
+        TypeName::Int(bits) => Ok(IRType::integer(*bits, true)),
+        TypeName::Bool => Ok(IRType::Bool),
+        TypeName::Address => Ok(IRType::Address),
+        TypeName::String => Ok(IRType::String),
+        _ => Err(format!("Unsupported type: {}", solidity_type)),
+    }
+}
+
+/// 同 [`convert_solidity_type_to_ir_type`]，不支持的类型报告为诊断信息。
+fn ir_type(solidity_type: &TypeName) -> Result<IRType, Diagnostic> {
+    convert_solidity_type_to_ir_type(solidity_type).map_err(|message| Diagnostic::error(codes::UNSUPPORTED, message))
+}
+
+/// 能否把 `from` 隐式转换为 `to`：符号相同且位宽不减小，或者无符号数转换为更宽的有符号数。
+fn implicitly_convertible(from: &IRType, to: &IRType) -> bool {
+    match (from.integer_bits(), to.integer_bits()) {
+        (Some(from_bits), Some(to_bits)) => {
+            if from.is_signed() == to.is_signed() {
+                from_bits <= to_bits
+            } else {
+                !from.is_signed() && from_bits < to_bits
+            }
+        }
+        _ => from == to,
+    }
+}
+
+/// 将语法树中的函数降级为 IR 函数。
+///
+/// 局部变量和参数以 `Value::Local` 表示，表达式的中间结果存放在
+/// `Value::Temporary` 中。函数体末尾没有 `return` 时会补上一个空返回。
+pub fn lower_function(func: &Function) -> Result<IRFunction, SemaError> {
+    let signatures = signatures(std::slice::from_ref(func), None)?;
+    lower_function_with_storage(func, func.name.clone(), &signatures, &StorageLayout::default(), &[])
+        .map_err(SemaError::from)
+}
+
+/// 将一组自由函数降级为 IR 函数，它们之间可以相互调用。
+pub fn lower_functions(functions: &[Function]) -> Result<Vec<IRFunction>, SemaError> {
+    let signatures = signatures(functions, None)?;
+    functions
+        .iter()
+        .map(|func| lower_function_with_storage(func, func.name.clone(), &signatures, &StorageLayout::default(), &[]))
+        .collect::<Result<_, _>>()
+        .map_err(SemaError::from)
+}
+
+/// 可被调用的函数的汇编符号名，以及参数和返回值类型。
+struct Signature {
+    symbol: String,
+    params: Vec<IRType>,
+    returns: Vec<IRType>,
+}
+
+/// 函数的汇编符号名。自由函数直接使用函数名，合约中的函数加上合约名作前缀，
+/// 如 `A.f`、`A.constructor`，这样不同合约的同名函数以及自由函数不会相互冲突。
+fn symbol(contract: Option<&str>, name: &str) -> String {
+    match contract {
+        Some(contract) => format!("{}.{}", contract, name),
+        None => name.to_string(),
+    }
+}
+
+/// 收集同一作用域（一个合约，或者所有自由函数）中各函数的签名。暂不支持重载。
+fn signatures(functions: &[Function], contract: Option<&str>) -> Result<HashMap<String, Signature>, Diagnostic> {
+    let mut signatures = HashMap::new();
+    for (index, func) in functions.iter().enumerate() {
+        let signature = Signature {
+            symbol: symbol(contract, &func.name),
+            params: func
+                .params
+                .iter()
+                .map(|(ty, _)| ir_type(ty))
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.or_label(func.span))?,
+            returns: func
+                .returns
+                .iter()
+                .map(ir_type)
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.or_label(func.span))?,
+        };
+        if signatures.insert(func.name.clone(), signature).is_some() {
+            let first = functions[..index].iter().find(|other| other.name == func.name);
+            let mut error = Diagnostic::error(
+                codes::DUPLICATE_DECLARATION,
+                format!("Function {} is declared more than once; overloading is not supported", func.name),
+            )
+            .with_label(func.span, "redeclared here");
+            if let Some(first) = first {
+                error = error.with_secondary_label(first.span, "first declared here");
+            }
+            return Err(error);
+        }
+    }
+    Ok(signatures)
+}
+
+/// 将合约中的构造函数和成员函数降级为 IR 函数，状态变量按存储布局读写。
+///
+/// 带初始值的状态变量在构造函数开头写入存储；合约没有声明构造函数时为此生成一个。
+pub fn lower_contract(contract: &Contract) -> Result<Vec<IRFunction>, SemaError> {
+    let storage = StorageLayout::new(&contract.state_variables)?;
+    let signatures = signatures(&contract.functions, Some(&contract.name))?;
+    let mut functions = Vec::new();
+
+    let implicit_constructor;
+    let constructor = match &contract.constructor {
+        Some(constructor) => Some(constructor),
+        None if contract.state_variables.iter().any(|var| var.initializer.is_some()) => {
+            implicit_constructor = Function {
+                name: "constructor".to_string(),
+                params: Vec::new(),
+                returns: Vec::new(),
+                body: Vec::new(),
+                span: Span::default(),
+            };
+            Some(&implicit_constructor)
+        }
+        None => None,
+    };
+    if let Some(constructor) = constructor {
+        functions.push(lower_function_with_storage(
+            constructor,
+            symbol(Some(&contract.name), "constructor"),
+            &signatures,
+            &storage,
+            &contract.state_variables,
+        )?);
+    }
+
+    for func in &contract.functions {
+        let symbol = signatures[&func.name].symbol.clone();
+        functions.push(lower_function_with_storage(func, symbol, &signatures, &storage, &[])?);
+    }
+    Ok(functions)
+}
+
+/// 依次降级源文件中的所有合约。合约名用作符号前缀，不能重复。
+pub fn lower_contracts(contracts: &[Contract]) -> Result<Vec<IRFunction>, SemaError> {
+    for (index, contract) in contracts.iter().enumerate() {
+        if let Some(first) = contracts[..index].iter().find(|other| other.name == contract.name) {
+            return Err(Diagnostic::error(
+                codes::DUPLICATE_DECLARATION,
+                format!("Contract {} is declared more than once", contract.name),
+            )
+            .with_label(contract.span, "redeclared here")
+            .with_secondary_label(first.span, "first declared here")
+            .into());
+        }
+    }
+
+    let mut functions = Vec::new();
+    for contract in contracts {
+        functions.extend(lower_contract(contract)?);
+    }
+    Ok(functions)
+}
+
+fn lower_function_with_storage(
+    func: &Function,
+    symbol: String,
+    signatures: &HashMap<String, Signature>,
+    storage: &StorageLayout,
+    initializers: &[StateVariable],
+) -> Result<IRFunction, Diagnostic> {
+    let mut ir_func = IRFunction::new(symbol);
+
+    for return_type in &func.returns {
+        ir_func.returns.push(ir_type(return_type).map_err(|e| e.or_label(func.span))?);
+    }
+
+    // 状态变量的初始值在声明参数之前降级，其中的名字总是指向状态变量，
+    // 即使构造函数的参数与它们同名
+    let mut lowerer = Lowerer::new(&mut ir_func, signatures, storage);
+    for var in initializers {
+        if let Some(initializer) = &var.initializer {
+            lowerer
+                .lower_initializer(var, initializer)
+                .map_err(|e| e.or_label(var.span))?;
+        }
+    }
+    // 参数与函数体最外层的局部变量在同一个作用域中
+    for (param_type, param_name) in &func.params {
+        let ir_type = ir_type(param_type).map_err(|e| e.or_label(func.span))?;
+        lowerer.declare(param_name, func.span)?;
+        lowerer.func.params.push((param_name.clone(), ir_type));
+    }
+    for stmt in &func.body {
+        lowerer.lower_statement(stmt)?;
+    }
+    lowerer.finish().map_err(|e| e.or_label(func.span))?;
+
+    Ok(ir_func)
+}
+
+struct Lowerer<'f> {
+    func: &'f mut IRFunction,
+    signatures: &'f HashMap<String, Signature>,
+    storage: &'f StorageLayout,
+    current_label: String,
+    instructions: Vec<Instruction>,
+    next_block: u32,
+    dead: bool, // 当前块之前是 return、break 或 continue
+    loops: Vec<LoopTargets>,
+    function_scope: Scope, // 参数和函数体最外层的局部变量
+    scopes: Vec<Scope>, // 嵌套的代码块，最内层在最后
+}
+
+/// 一层作用域中声明的变量：源码中的名字 → IR 中的名字和声明的位置。
+type Scope = HashMap<String, (String, Span)>;
+
+/// 赋值的目标：局部变量或参数，或者已经求出槽号的状态变量。
+enum Place {
+    Local(String, IRType),
+    Storage(Value, u8, IRType), // slot, offset, type
+}
+
+impl Place {
+    fn ir_type(&self) -> &IRType {
+        match self {
+            Place::Local(_, ty) | Place::Storage(_, _, ty) => ty,
+        }
+    }
+}
+
+/// 循环中 `continue` 和 `break` 跳转的目标块。
+struct LoopTargets {
+    continue_label: String,
+    break_label: String,
+}
+
+impl<'f> Lowerer<'f> {
+    fn new(func: &'f mut IRFunction, signatures: &'f HashMap<String, Signature>, storage: &'f StorageLayout) -> Self {
+        Self {
+            func,
+            signatures,
+            storage,
+            current_label: "entry".to_string(),
+            instructions: Vec::new(),
+            next_block: 0,
+            dead: false,
+            loops: Vec::new(),
+            function_scope: Scope::new(),
+            scopes: Vec::new(),
+        }
+    }
+
+    /// 以给定的终结指令结束当前基本块，并开启一个新块。新块紧跟在 return、
+    /// break 或 continue 之后，不会有跳转指向它。
+    fn terminate(&mut self, terminator: Terminator) {
+        let next = self.new_label();
+        self.switch_to(terminator, next);
+        self.dead = true;
+    }
+
+    fn new_label(&mut self) -> String {
+        self.next_block += 1;
+        format!("bb{}", self.next_block)
+    }
+
+    /// 以给定的终结指令结束当前基本块，并从标签为 `next` 的块继续。
+    /// return 等语句之后的空块直接丢弃。
+    fn switch_to(&mut self, terminator: Terminator, next: String) {
+        let label = std::mem::replace(&mut self.current_label, next);
+        let instructions = std::mem::take(&mut self.instructions);
+        if std::mem::take(&mut self.dead) && instructions.is_empty() {
+            return;
+        }
+        self.func.add_block(BasicBlock {
+            label,
+            instructions,
+            terminator,
+        });
+    }
+
+    /// 入口块，或者已有跳转指向的块。只在函数末尾使用，这时不会再有新的跳转。
+    fn is_reachable(&self, label: &str) -> bool {
+        label == "entry"
+            || self
+                .func
+                .blocks
+                .iter()
+                .any(|block| block.terminator.successors().iter().any(|succ| *succ == label))
+    }
+
+    fn finish(mut self) -> Result<(), Diagnostic> {
+        // 函数末尾隐式返回各返回类型的零值；return 之后的空块不再保留
+        if self.is_reachable(&self.current_label) || !self.instructions.is_empty() {
+            let values = self
+                .func
+                .returns
+                .iter()
+                .map(|ty| {
+                    Constant::zero(ty)
+                        .map(Value::Constant)
+                        .ok_or_else(|| {
+                            Diagnostic::error(codes::UNSUPPORTED, format!("Unsupported return type: {:?}", ty))
+                        })
+                })
+                .collect::<Result<_, _>>()?;
+            self.terminate(Terminator::Return(values));
+        }
+        Ok(())
+    }
+
+    /// 降低一条语句。错误没有更精确的位置时指向整条语句。
+    fn lower_statement(&mut self, stmt: &Statement) -> Result<(), Diagnostic> {
+        self.lower_statement_kind(stmt).map_err(|e| e.or_label(stmt.span))
+    }
+
+    fn lower_statement_kind(&mut self, stmt: &Statement) -> Result<(), Diagnostic> {
+        match &stmt.kind {
+            StatementKind::VariableDecl(var_type, name, initializer) => {
+                let ir_type = ir_type(var_type)?;
+                let value = match initializer {
+                    Some(expr) => {
+                        let value = self.lower_expr(expr)?;
+                        self.coerce(value, &ir_type).map_err(|e| e.or_label(expr.span))?
+                    }
+                    None => Constant::zero(&ir_type).map(Value::Constant).ok_or_else(|| {
+                        Diagnostic::error(codes::UNSUPPORTED, format!("Unsupported variable type: {}", var_type))
+                    })?,
+                };
+                let name = self.declare(name, stmt.span)?;
+                self.func.add_local(name.clone(), ir_type);
+                self.instructions.push(Instruction::Move(Value::Local(name), value));
+            }
+            StatementKind::Assignment(target, expr) => self.lower_assignment(target, expr)?,
+            StatementKind::CompoundAssignment(target, op, expr) => {
+                // 目标只求值一次，槽号等在读写之间共用
+                let place = self.lower_place(target)?;
+                let current = self.load_place(&place);
+                let value = self.lower_expr(expr)?;
+                let result = self.lower_binary_op(current, op, value)?;
+                self.store_place(&place, result)?;
+            }
+            // 结果不被使用的后缀自增自减与前缀形式相同，不必保留旧值
+            StatementKind::Expression(expr @ Expr { kind: ExprKind::Postfix(op, target), .. }) => {
+                self.lower_increment(target, op, true).map_err(|e| e.or_label(expr.span))?;
+            }
+            // 没有返回值的函数只能作为语句调用
+            StatementKind::Expression(expr @ Expr { kind: ExprKind::Call(name, args), .. }) => {
+                self.lower_call(name, args).map_err(|e| e.or_label(expr.span))?;
+            }
+            StatementKind::Expression(expr) => {
+                self.lower_expr(expr)?;
+            }
+            StatementKind::Return(expr) => {
+                let elements = match expr {
+                    Some(Expr { kind: ExprKind::Tuple(elements), .. }) => elements.iter().collect(),
+                    Some(expr) => vec![expr],
+                    None => Vec::new(),
+                };
+                let values = elements
+                    .iter()
+                    .map(|e| self.lower_expr(e))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if values.len() != self.func.returns.len() {
+                    return Err(Diagnostic::error(
+                        codes::TYPE_MISMATCH,
+                        format!(
+                            "Function {} returns {} values, but {} were given",
+                            self.func.name,
+                            self.func.returns.len(),
+                            values.len()
+                        ),
+                    ));
+                }
+                let returns = self.func.returns.clone();
+                let values = values
+                    .into_iter()
+                    .zip(&returns)
+                    .zip(&elements)
+                    .map(|((value, ty), e)| self.coerce(value, ty).map_err(|err| err.or_label(e.span)))
+                    .collect::<Result<_, _>>()?;
+                self.terminate(Terminator::Return(values));
+            }
+            StatementKind::If(condition, then_branch, else_branch) => {
+                let condition = self.lower_condition(condition)?;
+                let then_label = self.new_label();
+                let else_label = else_branch.as_ref().map(|_| self.new_label());
+                let merge_label = self.new_label();
+
+                let false_label = else_label.clone().unwrap_or_else(|| merge_label.clone());
+                self.switch_to(Terminator::Branch(condition, then_label.clone(), false_label), then_label);
+                self.lower_block(then_branch)?;
+
+                if let (Some(else_branch), Some(else_label)) = (else_branch, else_label) {
+                    self.switch_to(Terminator::Jump(merge_label.clone()), else_label);
+                    self.lower_block(else_branch)?;
+                }
+                self.switch_to(Terminator::Jump(merge_label.clone()), merge_label);
+            }
+            StatementKind::While(condition, body) => {
+                let header_label = self.new_label();
+                let body_label = self.new_label();
+                let exit_label = self.new_label();
+
+                self.switch_to(Terminator::Jump(header_label.clone()), header_label.clone());
+                let condition = self.lower_condition(condition)?;
+                self.switch_to(
+                    Terminator::Branch(condition, body_label.clone(), exit_label.clone()),
+                    body_label,
+                );
+                self.lower_loop_body(body, &header_label, &exit_label)?;
+                self.switch_to(Terminator::Jump(header_label), exit_label);
+            }
+            StatementKind::DoWhile(body, condition) => {
+                let body_label = self.new_label();
+                let latch_label = self.new_label();
+                let exit_label = self.new_label();
+
+                self.switch_to(Terminator::Jump(body_label.clone()), body_label.clone());
+                self.lower_loop_body(body, &latch_label, &exit_label)?;
+                self.switch_to(Terminator::Jump(latch_label.clone()), latch_label);
+                let condition = self.lower_condition(condition)?;
+                self.switch_to(Terminator::Branch(condition, body_label, exit_label.clone()), exit_label);
+            }
+            // 初始化部分声明的变量只在循环中可见
+            StatementKind::For(init, condition, update, body) => {
+                self.in_scope(|lowerer| lowerer.lower_for(init.as_deref(), condition.as_ref(), update.as_deref(), body))?
+            }
+            StatementKind::Block(statements) => self.lower_block(statements)?,
+            StatementKind::Break => {
+                let target = self.loops.last().map(|targets| targets.break_label.clone());
+                let target = target.ok_or_else(|| {
+                    Diagnostic::error(codes::INVALID_CONTROL_FLOW, "'break' outside of a loop")
+                        .with_label(stmt.span, "cannot `break` outside of a loop")
+                })?;
+                self.terminate(Terminator::Jump(target));
+            }
+            StatementKind::Continue => {
+                let target = self.loops.last().map(|targets| targets.continue_label.clone());
+                let target = target.ok_or_else(|| {
+                    Diagnostic::error(codes::INVALID_CONTROL_FLOW, "'continue' outside of a loop")
+                        .with_label(stmt.span, "cannot `continue` outside of a loop")
+                })?;
+                self.terminate(Terminator::Jump(target));
+            }
+        }
+        Ok(())
+    }
+
+    /// 降低循环体，其中的 `continue` 和 `break` 分别跳到给定的块。
+    fn lower_for(
+        &mut self,
+        init: Option<&Statement>,
+        condition: Option<&Expr>,
+        update: Option<&Statement>,
+        body: &[Statement],
+    ) -> Result<(), Diagnostic> {
+        if let Some(init) = init {
+            self.lower_statement(init)?;
+        }
+        let header_label = self.new_label();
+        let body_label = self.new_label();
+        let latch_label = self.new_label();
+        let exit_label = self.new_label();
+
+        self.switch_to(Terminator::Jump(header_label.clone()), header_label.clone());
+        // 省略条件时循环只能通过 break 或 return 退出
+        let header_exit = match condition {
+            Some(condition) => {
+                let condition = self.lower_condition(condition)?;
+                Terminator::Branch(condition, body_label.clone(), exit_label.clone())
+            }
+            None => Terminator::Jump(body_label.clone()),
+        };
+        self.switch_to(header_exit, body_label);
+        self.lower_loop_body(body, &latch_label, &exit_label)?;
+        self.switch_to(Terminator::Jump(latch_label.clone()), latch_label);
+        if let Some(update) = update {
+            self.lower_statement(update)?;
+        }
+        self.switch_to(Terminator::Jump(header_label), exit_label);
+        Ok(())
+    }
+
+    fn lower_loop_body(&mut self, body: &[Statement], continue_label: &str, break_label: &str) -> Result<(), Diagnostic> {
+        self.loops.push(LoopTargets {
+            continue_label: continue_label.to_string(),
+            break_label: break_label.to_string(),
+        });
+        let result = self.lower_block(body);
+        self.loops.pop();
+        result
+    }
+
+    /// 在新的作用域中降低代码块，其中声明的变量在块结束后不再可见。
+    fn lower_block(&mut self, statements: &[Statement]) -> Result<(), Diagnostic> {
+        self.in_scope(|lowerer| statements.iter().try_for_each(|stmt| lowerer.lower_statement(stmt)))
+    }
+
+    fn in_scope(&mut self, f: impl FnOnce(&mut Self) -> Result<(), Diagnostic>) -> Result<(), Diagnostic> {
+        self.scopes.push(Scope::new());
+        let result = f(self);
+        self.scopes.pop();
+        result
+    }
+
+    /// 在当前作用域中声明变量，返回它在 IR 中的名字。
+    ///
+    /// 同一作用域中不能重复声明。内层作用域可以遮蔽外层的同名变量，此时改用
+    /// `a.1` 这样的名字（标识符中不会有 `.`），使每个变量有自己的类型。
+    fn declare(&mut self, name: &str, span: Span) -> Result<String, Diagnostic> {
+        let scope = self.scopes.last_mut().unwrap_or(&mut self.function_scope);
+        if let Some((_, first)) = scope.get(name) {
+            let mut error = Diagnostic::error(
+                codes::DUPLICATE_DECLARATION,
+                format!("Variable {} is already declared in this scope", name),
+            )
+            .with_label(span, "redeclared here");
+            // 参数没有各自的位置，都指向整个函数
+            if *first != span {
+                error = error.with_secondary_label(*first, "first declared here");
+            }
+            return Err(error);
+        }
+
+        let in_use = |ir_name: &str| {
+            self.func.locals.contains_key(ir_name) || self.func.params.iter().any(|(p, _)| p == ir_name)
+        };
+        let mut ir_name = name.to_string();
+        for n in 1.. {
+            if !in_use(&ir_name) {
+                break;
+            }
+            ir_name = format!("{}.{}", name, n);
+        }
+        scope.insert(name.to_string(), (ir_name.clone(), span));
+        Ok(ir_name)
+    }
+
+    /// 按作用域从内到外查找变量在 IR 中的名字；找不到时可能是状态变量。
+    fn resolve(&self, name: &str) -> Option<&str> {
+        self.scopes
+            .iter()
+            .rev()
+            .chain([&self.function_scope])
+            .find_map(|scope| scope.get(name))
+            .map(|(ir_name, _)| ir_name.as_str())
+    }
+
+    /// 条件表达式必须是 bool 类型。
+    fn lower_condition(&mut self, expr: &Expr) -> Result<Value, Diagnostic> {
+        self.lower_bool(expr, "Condition")
+    }
+
+    fn lower_bool(&mut self, expr: &Expr, what: &str) -> Result<Value, Diagnostic> {
+        let value = self.lower_expr(expr)?;
+        match self.func.value_type(&value) {
+            Some(IRType::Bool) => Ok(value),
+            Some(ty) => Err(Diagnostic::error(
+                codes::TYPE_MISMATCH,
+                format!("{} must be of type bool, found {:?}", what, ty),
+            )
+            .with_label(expr.span, format!("expected bool, found {:?}", ty))),
+            None => Err(Diagnostic::error(codes::TYPE_MISMATCH, format!("{} must be of type bool", what))
+                .with_label(expr.span, "expected bool")),
+        }
+    }
+
+    /// `&&` 和 `||` 短路求值：右侧只在结果还不确定时求值。两条路径的结果
+    /// 写入同一个编译器生成的局部变量，由 SSA 构造在汇合块中合并。
+    fn lower_logical(&mut self, lhs: &Expr, op: &BinaryOp, rhs: &Expr) -> Result<Value, Diagnostic> {
+        let what = match op {
+            BinaryOp::LogicalAnd => "Operand of &&",
+            _ => "Operand of ||",
+        };
+        let lhs = self.lower_bool(lhs, what)?;
+        let rhs_label = self.new_label();
+        let end_label = self.new_label();
+
+        let result = self.hidden_local(&end_label, IRType::Bool);
+        self.instructions.push(Instruction::Move(result.clone(), lhs.clone()));
+
+        let (true_label, false_label) = match op {
+            BinaryOp::LogicalAnd => (rhs_label.clone(), end_label.clone()),
+            _ => (end_label.clone(), rhs_label.clone()),
+        };
+        self.switch_to(Terminator::Branch(lhs, true_label, false_label), rhs_label);
+        let rhs = self.lower_bool(rhs, what)?;
+        self.instructions.push(Instruction::Move(result.clone(), rhs));
+        self.switch_to(Terminator::Jump(end_label.clone()), end_label);
+
+        Ok(result)
+    }
+
+    /// 编译器生成的局部变量。标识符不能以 % 开头，不会与源码中的变量重名。
+    fn hidden_local(&mut self, name: &str, ty: IRType) -> Value {
+        let name = format!("%{}", name);
+        self.func.add_local(name.clone(), ty);
+        Value::Local(name)
+    }
+
+    /// 降低一个表达式。错误没有更精确的位置时指向整个表达式。
+    fn lower_expr(&mut self, expr: &Expr) -> Result<Value, Diagnostic> {
+        self.lower_expr_kind(expr).map_err(|e| e.or_label(expr.span))
+    }
+
+    fn lower_expr_kind(&mut self, expr: &Expr) -> Result<Value, Diagnostic> {
+        // 字面量表达式在编译期求值
+        if let Some(constant) = literal::fold(expr) {
+            return Ok(Value::Constant(constant?));
+        }
+        match &expr.kind {
+            ExprKind::Number(n) => Ok(Value::int(n.clone())),
+            ExprKind::Boolean(b) => Ok(Value::Constant(Constant::Bool(*b))),
+            ExprKind::String(_) => Err(Diagnostic::error(codes::UNSUPPORTED, "String literals are not supported yet")),
+            ExprKind::Identifier(_) | ExprKind::Index(..) => {
+                let place = self.lower_place(expr)?;
+                Ok(self.load_place(&place))
+            }
+            ExprKind::Prefix(op @ (UnaryOp::Increment | UnaryOp::Decrement), target) => {
+                self.lower_increment(target, op, true)
+            }
+            ExprKind::Postfix(op, target) => self.lower_increment(target, op, false),
+            ExprKind::Prefix(op, operand) => self.lower_unary_op(op, operand),
+            ExprKind::BinaryOp(lhs, op @ (BinaryOp::LogicalAnd | BinaryOp::LogicalOr), rhs) => {
+                self.lower_logical(lhs, op, rhs)
+            }
+            ExprKind::BinaryOp(lhs, op, rhs) => {
+                let lhs = self.lower_expr(lhs)?;
+                let rhs = self.lower_expr(rhs)?;
+                self.lower_binary_op(lhs, op, rhs)
+            }
+            ExprKind::Call(name, args) => self
+                .lower_call(name, args)?
+                .ok_or_else(|| {
+                    Diagnostic::error(codes::TYPE_MISMATCH, format!("Function {} does not return a value", name))
+                }),
+            ExprKind::Tuple(_) => Err(Diagnostic::error(
+                codes::UNSUPPORTED,
+                "Tuples are only supported in return statements",
+            )),
+        }
+    }
+
+    /// 调用同一作用域中的函数，返回调用结果；没有返回值时为 `None`。
+    ///
+    /// 实参按形参类型隐式转换，字面量先放进一个形参类型的临时值，
+    /// 这样每个实参都有确定的类型，256 位的实参也总有槽位可以按引用传递。
+    fn lower_call(&mut self, name: &str, args: &[Expr]) -> Result<Option<Value>, Diagnostic> {
+        if self.resolve(name).is_some() {
+            return Err(Diagnostic::error(codes::TYPE_MISMATCH, format!("{} is not a function", name)));
+        }
+        let signature = self.signatures.get(name).ok_or_else(|| {
+            Diagnostic::error(codes::UNDECLARED_IDENTIFIER, format!("Undeclared function: {}", name))
+        })?;
+        if args.len() != signature.params.len() {
+            return Err(Diagnostic::error(
+                codes::WRONG_ARGUMENT_COUNT,
+                format!(
+                    "Function {} expects {} arguments, but {} were given",
+                    name,
+                    signature.params.len(),
+                    args.len()
+                ),
+            ));
+        }
+
+        let mut values = Vec::new();
+        for (arg, ty) in args.iter().zip(&signature.params) {
+            let value = self.lower_expr(arg)?;
+            let mut value = self.coerce(value, ty).map_err(|e| e.or_label(arg.span))?;
+            if let Value::Constant(_) = value {
+                let temp = self.func.new_temp(ty.clone());
+                self.instructions.push(Instruction::Move(temp.clone(), value));
+                value = temp;
+            }
+            values.push(value);
+        }
+
+        let dest = match signature.returns.as_slice() {
+            [] => None,
+            [ty] => Some(self.func.new_temp(ty.clone())),
+            _ => {
+                return Err(Diagnostic::error(
+                    codes::UNSUPPORTED,
+                    format!(
+                        "Function {} returns {} values; calls to functions with multiple return values are not supported",
+                        name,
+                        signature.returns.len()
+                    ),
+                ))
+            }
+        };
+        self.instructions
+            .push(Instruction::Call(dest.clone(), signature.symbol.clone(), values));
+        Ok(dest)
+    }
+
+    fn lower_binary_op(&mut self, lhs: Value, op: &BinaryOp, rhs: Value) -> Result<Value, Diagnostic> {
+        let (build, compare): (fn(Value, Value, Value) -> Instruction, bool) = match op {
+            BinaryOp::Add => (Instruction::Add, false),
+            BinaryOp::Subtract => (Instruction::Sub, false),
+            BinaryOp::Multiply => (Instruction::Mul, false),
+            BinaryOp::Divide => (Instruction::Div, false),
+            BinaryOp::Modulo => (Instruction::Mod, false),
+            BinaryOp::BitAnd => (Instruction::And, false),
+            BinaryOp::BitOr => (Instruction::Or, false),
+            BinaryOp::BitXor => (Instruction::Xor, false),
+            BinaryOp::Equals => (Instruction::Eq, true),
+            BinaryOp::NotEquals => (Instruction::Ne, true),
+            BinaryOp::GreaterThan => (Instruction::Gt, true),
+            BinaryOp::LessThan => (Instruction::Lt, true),
+            BinaryOp::GreaterThanOrEqual => (Instruction::Ge, true),
+            BinaryOp::LessThanOrEqual => (Instruction::Le, true),
+            BinaryOp::ShiftLeft => return self.lower_shift(lhs, op, Instruction::Shl, rhs),
+            BinaryOp::ShiftRight => return self.lower_shift(lhs, op, Instruction::Shr, rhs),
+            BinaryOp::Power => return self.lower_power(lhs, rhs),
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr => {
+                return Err(Diagnostic::error(
+                    codes::UNSUPPORTED,
+                    format!("Operator {:?} must be evaluated with short-circuiting", op),
+                ))
+            }
+        };
+
+        // 字面量没有确定的类型，运算类型取自另一侧的操作数；
+        // 两侧类型不同时，较窄的一侧隐式转换为另一侧的类型
+        let lhs_type = self.func.value_type(&lhs);
+        let rhs_type = self.func.value_type(&rhs);
+        let operand_type = match (&lhs, &rhs, lhs_type, rhs_type) {
+            (Value::Constant(_), _, _, Some(ty)) => ty,
+            (_, _, Some(lhs_ty), Some(rhs_ty))
+                if !matches!(rhs, Value::Constant(_)) && implicitly_convertible(&lhs_ty, &rhs_ty) =>
+            {
+                rhs_ty
+            }
+            (_, _, Some(ty), _) => ty,
+            _ => IRType::Int(64),
+        };
+        if !compare && operand_type.integer_bits().is_none() {
+            return Err(Diagnostic::error(
+                codes::TYPE_MISMATCH,
+                format!("Operator {:?} requires integer operands, found {:?}", op, operand_type),
+            ));
+        }
+        let lhs = self.coerce(lhs, &operand_type)?;
+        let rhs = self.coerce(rhs, &operand_type)?;
+
+        let ty = if compare { IRType::Bool } else { operand_type };
+        let (dest, inst) = self.func.build_binary(build, ty, lhs, rhs);
+        self.instructions.push(inst);
+
+        Ok(dest)
+    }
+
+    /// 移位和乘方的结果类型取自左侧操作数，右侧可以是任意无符号整数，两侧不做隐式转换。
+    /// 左侧是字面量时按 Solidity 的规则取 uint256，负数取 int256。
+    fn shift_operand_type(&self, lhs: &Value, op: &BinaryOp, rhs: &Value) -> Result<IRType, Diagnostic> {
+        let rhs_unsigned = match rhs {
+            Value::Constant(Constant::Int(n)) => n.sign() != Sign::Minus,
+            _ => self
+                .func
+                .value_type(rhs)
+                .is_some_and(|ty| ty.integer_bits().is_some() && !ty.is_signed()),
+        };
+        if !rhs_unsigned {
+            return Err(Diagnostic::error(
+                codes::TYPE_MISMATCH,
+                format!("Right operand of {:?} must be an unsigned integer", op),
+            ));
+        }
+        match lhs {
+            Value::Constant(Constant::Int(n)) if n.sign() == Sign::Minus => Ok(IRType::Int(256)),
+            Value::Constant(Constant::Int(_)) => Ok(IRType::Uint(256)),
+            _ => self
+                .func
+                .value_type(lhs)
+                .filter(|ty| ty.integer_bits().is_some())
+                .ok_or_else(|| {
+                    Diagnostic::error(codes::TYPE_MISMATCH, format!("Operator {:?} requires an integer left operand", op))
+                }),
+        }
+    }
+
+    fn lower_shift(
+        &mut self,
+        lhs: Value,
+        op: &BinaryOp,
+        build: fn(Value, Value, Value) -> Instruction,
+        rhs: Value,
+    ) -> Result<Value, Diagnostic> {
+        let ty = self.shift_operand_type(&lhs, op, &rhs)?;
+        let lhs = self.coerce(lhs, &ty)?;
+        let (dest, inst) = self.func.build_binary(build, ty, lhs, rhs);
+        self.instructions.push(inst);
+        Ok(dest)
+    }
+
+    /// `base ** exponent` 按平方乘计算，结果按 base 的类型回绕。指数是字面量时展开成不含
+    /// 循环的乘法序列，否则生成一个逐位处理指数的循环。
+    fn lower_power(&mut self, base: Value, exponent: Value) -> Result<Value, Diagnostic> {
+        let ty = self.shift_operand_type(&base, &BinaryOp::Power, &exponent)?;
+        let base = self.coerce(base, &ty)?;
+
+        if let Value::Constant(Constant::Int(exp)) = &exponent {
+            let mut base = base;
+            let mut result = None;
+            for bit in 0..exp.bits() {
+                if bit > 0 {
+                    base = self.push_mul(&ty, base.clone(), base);
+                }
+                if exp.bit(bit) {
+                    result = Some(match result {
+                        Some(result) => self.push_mul(&ty, result, base.clone()),
+                        None => base.clone(),
+                    });
+                }
+            }
+            return Ok(match result {
+                Some(result) => result,
+                None => {
+                    // x ** 0 == 1
+                    let one = self.func.new_temp(ty);
+                    self.instructions.push(Instruction::Move(one.clone(), Value::int(1)));
+                    one
+                }
+            });
+        }
+
+        let exp_type = self
+            .func
+            .value_type(&exponent)
+            .ok_or_else(|| Diagnostic::error(codes::TYPE_MISMATCH, "Right operand of Power must be an unsigned integer"))?;
+        let header_label = self.new_label();
+        let body_label = self.new_label();
+        let multiply_label = self.new_label();
+        let step_label = self.new_label();
+        let exit_label = self.new_label();
+
+        let result = self.hidden_local(&format!("{}.result", exit_label), ty.clone());
+        let power = self.hidden_local(&format!("{}.base", exit_label), ty.clone());
+        let exp = self.hidden_local(&format!("{}.exponent", exit_label), exp_type.clone());
+        self.instructions
+            .push(Instruction::Move(result.clone(), Value::int(1)));
+        self.instructions.push(Instruction::Move(power.clone(), base));
+        self.instructions.push(Instruction::Move(exp.clone(), exponent));
+        self.switch_to(Terminator::Jump(header_label.clone()), header_label.clone());
+
+        // while (exp != 0)
+        let (nonzero, inst) =
+            self.func
+                .build_binary(Instruction::Ne, IRType::Bool, exp.clone(), Value::int(0));
+        self.instructions.push(inst);
+        self.switch_to(
+            Terminator::Branch(nonzero, body_label.clone(), exit_label.clone()),
+            body_label,
+        );
+
+        // if (exp & 1 != 0) result *= base
+        let (bit, inst) =
+            self.func
+                .build_binary(Instruction::And, exp_type.clone(), exp.clone(), Value::int(1));
+        self.instructions.push(inst);
+        let (odd, inst) = self
+            .func
+            .build_binary(Instruction::Ne, IRType::Bool, bit, Value::int(0));
+        self.instructions.push(inst);
+        self.switch_to(
+            Terminator::Branch(odd, multiply_label.clone(), step_label.clone()),
+            multiply_label,
+        );
+        let product = self.push_mul(&ty, result.clone(), power.clone());
+        self.instructions.push(Instruction::Move(result.clone(), product));
+        self.switch_to(Terminator::Jump(step_label.clone()), step_label);
+
+        // base *= base; exp >>= 1
+        let square = self.push_mul(&ty, power.clone(), power.clone());
+        self.instructions.push(Instruction::Move(power, square));
+        let (shifted, inst) =
+            self.func
+                .build_binary(Instruction::Shr, exp_type, exp.clone(), Value::int(1));
+        self.instructions.push(inst);
+        self.instructions.push(Instruction::Move(exp, shifted));
+        self.switch_to(Terminator::Jump(header_label), exit_label);
+
+        Ok(result)
+    }
+
+    fn push_mul(&mut self, ty: &IRType, lhs: Value, rhs: Value) -> Value {
+        let (dest, inst) = self.func.build_binary(Instruction::Mul, ty.clone(), lhs, rhs);
+        self.instructions.push(inst);
+        dest
+    }
+
+    /// 把值隐式转换为类型 `ty`：整数拓宽时插入 `Extend`，字面量检查能否用该类型表示。
+    fn coerce(&mut self, value: Value, ty: &IRType) -> Result<Value, Diagnostic> {
+        if let Value::Constant(Constant::Int(n)) = &value {
+            if !ty.can_represent(n) {
+                return Err(Diagnostic::error(
+                    codes::TYPE_MISMATCH,
+                    format!("Literal {} does not fit in type {:?}", n, ty),
+                ));
+            }
+            return Ok(value);
+        }
+        let Some(from) = self.func.value_type(&value) else {
+            return Ok(value);
+        };
+        if from == *ty || matches!(value, Value::Constant(_)) {
+            return Ok(value);
+        }
+        if !implicitly_convertible(&from, ty) {
+            return Err(Diagnostic::error(
+                codes::TYPE_MISMATCH,
+                format!("Cannot implicitly convert {:?} to {:?}", from, ty),
+            ));
+        }
+        let dest = self.func.new_temp(ty.clone());
+        self.instructions.push(Instruction::Extend(dest.clone(), value));
+        Ok(dest)
+    }
+
+    /// `-a`、`!a` 和 `~a`。作用于字面量时直接得到常量。
+    fn lower_unary_op(&mut self, op: &UnaryOp, operand: &Expr) -> Result<Value, Diagnostic> {
+        if let (UnaryOp::Not, ExprKind::Boolean(b)) = (op, &operand.kind) {
+            return Ok(Value::Constant(Constant::Bool(!b)));
+        }
+
+        if let UnaryOp::Not = op {
+            let value = self.lower_bool(operand, "Operand of !")?;
+            let (dest, inst) =
+                self.func
+                    .build_binary(Instruction::Eq, IRType::Bool, value, Value::Constant(Constant::Bool(false)));
+            self.instructions.push(inst);
+            return Ok(dest);
+        }
+
+        let value = self.lower_expr(operand)?;
+        let ty = self
+            .func
+            .value_type(&value)
+            .filter(|ty| ty.integer_bits().is_some())
+            .ok_or_else(|| {
+                Diagnostic::error(codes::TYPE_MISMATCH, format!("Operator {:?} requires an integer operand", op))
+                    .with_label(operand.span, "expected an integer")
+            })?;
+        let dest = self.func.new_temp(ty.clone());
+        match op {
+            UnaryOp::Negate if !ty.is_signed() => {
+                return Err(Diagnostic::error(
+                    codes::TYPE_MISMATCH,
+                    format!("Unary minus is not allowed on unsigned type {:?}", ty),
+                )
+                .with_label(operand.span, format!("this has type {:?}", ty)))
+            }
+            UnaryOp::Negate => self.instructions.push(Instruction::Sub(
+                dest.clone(),
+                Value::int(0),
+                value,
+            )),
+            _ => self.instructions.push(Instruction::Not(dest.clone(), value)),
+        }
+        Ok(dest)
+    }
+
+    /// 前缀或后缀的 `++`、`--`。目标只求值一次；前缀形式得到新值，后缀形式得到旧值。
+    fn lower_increment(&mut self, target: &Expr, op: &UnaryOp, prefix: bool) -> Result<Value, Diagnostic> {
+        let place = self.lower_place(target)?;
+        let ty = place.ir_type().clone();
+        if ty.integer_bits().is_none() {
+            return Err(Diagnostic::error(
+                codes::TYPE_MISMATCH,
+                format!("Operator {:?} requires an integer operand", op),
+            )
+            .with_label(target.span, "expected an integer"));
+        }
+
+        let mut old = self.load_place(&place);
+        if !prefix && matches!(place, Place::Local(..)) {
+            // 局部变量随后会被改写，先保存旧值
+            let saved = self.func.new_temp(ty.clone());
+            self.instructions.push(Instruction::Move(saved.clone(), old));
+            old = saved;
+        }
+        let build = match op {
+            UnaryOp::Decrement => Instruction::Sub,
+            _ => Instruction::Add,
+        };
+        let (new, inst) = self
+            .func
+            .build_binary(build, ty, old.clone(), Value::int(1));
+        self.instructions.push(inst);
+        self.store_place(&place, new.clone())?;
+
+        Ok(if prefix { new } else { old })
+    }
+
+    /// 赋值给局部变量、参数或状态变量（包括映射的元素）。
+    fn lower_assignment(&mut self, target: &Expr, expr: &Expr) -> Result<(), Diagnostic> {
+        let value = self.lower_expr(expr)?;
+        let place = self.lower_place(target)?;
+        self.store_place(&place, value)
+    }
+
+    /// 把状态变量的初始值直接写入它所在的槽。
+    fn lower_initializer(&mut self, var: &StateVariable, initializer: &Expr) -> Result<(), Diagnostic> {
+        let value = self.lower_expr(initializer)?;
+        let target = Expr::new(ExprKind::Identifier(var.name.clone()), var.span);
+        let (slot, offset, ty) = self.storage_reference(&target)?;
+        let ty = self.storage_value_type(&ty)?;
+        self.store_place(&Place::Storage(slot, offset, ty), value)
+            .map_err(|e| e.or_label(initializer.span))
+    }
+
+    /// 求出赋值目标的位置。状态变量的槽号在这里算好，之后的读写都使用它。
+    fn lower_place(&mut self, target: &Expr) -> Result<Place, Diagnostic> {
+        let local = match &target.kind {
+            ExprKind::Identifier(name) => self.resolve(name).map(str::to_string),
+            _ => None,
+        };
+        match local {
+            Some(name) => {
+                let ty = self.func.value_type(&Value::Local(name.clone())).ok_or_else(|| {
+                    Diagnostic::error(codes::UNDECLARED_IDENTIFIER, format!("Undeclared variable: {}", name))
+                        .with_label(target.span, "not found in this scope")
+                })?;
+                Ok(Place::Local(name, ty))
+            }
+            None => {
+                let (slot, offset, ty) = self.storage_reference(target)?;
+                let ty = self.storage_value_type(&ty).map_err(|e| e.or_label(target.span))?;
+                Ok(Place::Storage(slot, offset, ty))
+            }
+        }
+    }
+
+    fn load_place(&mut self, place: &Place) -> Value {
+        match place {
+            Place::Local(name, _) => Value::Local(name.clone()),
+            Place::Storage(slot, offset, ty) => {
+                let dest = self.func.new_temp(ty.clone());
+                self.instructions
+                    .push(Instruction::StorageLoad(dest.clone(), slot.clone(), *offset));
+                dest
+            }
+        }
+    }
+
+    fn store_place(&mut self, place: &Place, value: Value) -> Result<(), Diagnostic> {
+        let value = self.coerce(value, place.ir_type())?;
+        match place {
+            Place::Local(name, _) => {
+                self.instructions
+                    .push(Instruction::Move(Value::Local(name.clone()), value));
+            }
+            Place::Storage(slot, offset, ty) => {
+                let mut value = value;
+                // 写入的字节数由值的类型决定，字面量先放进一个该类型的临时值
+                if let Value::Constant(_) = value {
+                    let temp = self.func.new_temp(ty.clone());
+                    self.instructions.push(Instruction::Move(temp.clone(), value));
+                    value = temp;
+                }
+                self.instructions
+                    .push(Instruction::StorageStore(slot.clone(), *offset, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// 求出状态变量或映射元素所在的槽号、槽内偏移和类型。
+    fn storage_reference(&mut self, expr: &Expr) -> Result<(Value, u8, TypeName), Diagnostic> {
+        match &expr.kind {
+            ExprKind::Identifier(name) => {
+                let var = self
+                    .storage
+                    .get(name)
+                    .ok_or_else(|| {
+                        Diagnostic::error(codes::UNDECLARED_IDENTIFIER, format!("Undeclared variable: {}", name))
+                            .with_label(expr.span, "not found in this scope")
+                    })?;
+                Ok((Value::int(var.slot), var.offset, var.var_type.clone()))
+            }
+            ExprKind::Index(base, index) => {
+                let (slot, _, ty) = self.storage_reference(base)?;
+                let TypeName::Mapping(key_type, value_type) = ty else {
+                    return Err(Diagnostic::error(
+                        codes::UNSUPPORTED,
+                        format!("Indexing values of type {} is not supported", ty),
+                    )
+                    .with_label(base.span, format!("this has type {}", ty)));
+                };
+                let key_type = ir_type(&key_type).map_err(|e| e.or_label(base.span))?;
+                let key = self.lower_expr(index)?;
+                let key = self.coerce(key, &key_type).map_err(|e| e.or_label(index.span))?;
+                let dest = self.func.new_temp(IRType::Uint(256));
+                self.instructions
+                    .push(Instruction::MappingSlot(dest.clone(), key, slot));
+                Ok((dest, 0, *value_type))
+            }
+            _ => Err(Diagnostic::error(codes::TYPE_MISMATCH, "Expression is not assignable")
+                .with_label(expr.span, "cannot assign to this expression")),
+        }
+    }
+
+    /// 可以整体读写的状态变量的 IR 类型；映射、数组等只能通过下标访问其元素。
+    fn storage_value_type(&self, ty: &TypeName) -> Result<IRType, Diagnostic> {
+        match ty {
+            TypeName::Mapping(..) | TypeName::Array(..) => {
+                Err(Diagnostic::error(
+                    codes::TYPE_MISMATCH,
+                    format!("State variable of type {} cannot be used as a value", ty),
+                ))
+            }
+            _ => ir_type(ty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::lexer::Lexer;
+    use crate::frontend::parser::Parser;
+    use num_bigint::BigInt;
+
+    /// 降级失败时的错误。源码必须能通过词法和语法分析。
+    fn lower_err(input: &str) -> SemaError {
+        let tokens: Vec<_> = Lexer::new(input).collect();
+        lower_functions(&Parser::new(tokens).parse().unwrap().functions).unwrap_err()
+    }
+
+    fn lower_contract_source(input: &str) -> Result<Vec<IRFunction>, Diagnostic> {
+        let tokens: Vec<_> = Lexer::new(input).collect();
+        let unit = Parser::new(tokens).parse().map_err(|mut errors| errors.remove(0))?;
+        lower_contract(&unit.contracts[0]).map_err(Diagnostic::from)
+    }
+
+    fn lower(input: &str) -> Vec<IRFunction> {
+        let tokens: Vec<_> = Lexer::new(input).collect();
+        let mut parser = Parser::new(tokens);
+        let functions = parser.parse().unwrap().functions;
+        lower_functions(&functions).unwrap()
+    }
+
+    #[test]
+    fn test_lower_return_expression() {
+        let funcs = lower("function add(uint a, uint b) returns (uint) { return a + b; }");
+        let func = &funcs[0];
+
+        assert_eq!(func.blocks.len(), 1);
+        let block = &func.blocks[0];
+        assert_eq!(block.label, "entry");
+        assert_eq!(
+            block.instructions,
+            vec![Instruction::Add(
+                Value::Temporary(0),
+                Value::Local("a".to_string()),
+                Value::Local("b".to_string()),
+            )]
+        );
+        assert_eq!(func.temporaries, vec![IRType::Uint(256)]);
+        assert_eq!(block.terminator, Terminator::Return(vec![Value::Temporary(0)]));
+    }
+
+    #[test]
+    fn test_lower_locals_and_implicit_return() {
+        let funcs = lower("function f() { uint x = 1; x = x * 2; }");
+        let func = &funcs[0];
+
+        assert!(func.locals.contains_key("x"));
+        assert_eq!(func.blocks.len(), 1);
+        assert_eq!(func.blocks[0].instructions.len(), 3);
+        assert_eq!(func.blocks[0].terminator, Terminator::Return(Vec::new()));
+    }
+
+    #[test]
+    fn test_lower_comparison_types() {
+        let funcs = lower(
+            "function f(uint a) returns (bool) { return a >= 1; } \
+             function g(int b) returns (bool) { return 2 <= b; }",
+        );
+
+        assert_eq!(funcs[0].params[0].1, IRType::Uint(256));
+        assert_eq!(funcs[0].temporaries, vec![IRType::Bool]);
+        assert!(matches!(
+            funcs[0].blocks[0].instructions.as_slice(),
+            [Instruction::Ge(Value::Temporary(0), _, _)]
+        ));
+        assert_eq!(funcs[1].params[0].1, IRType::Int(256));
+        assert!(matches!(
+            funcs[1].blocks[0].instructions.as_slice(),
+            [Instruction::Le(Value::Temporary(0), Value::Constant(_), _)]
+        ));
+    }
+
+    #[test]
+    fn test_lower_multiple_returns() {
+        let funcs = lower("function f(uint a, bool b) returns (uint, bool) { return (a, b); }");
+        assert_eq!(
+            funcs[0].blocks[0].terminator,
+            Terminator::Return(vec![Value::Local("a".to_string()), Value::Local("b".to_string())])
+        );
+
+        let funcs = lower("function g() returns (uint, bool) { }");
+        assert_eq!(
+            funcs[0].blocks[0].terminator,
+            Terminator::Return(vec![
+                Value::int(0),
+                Value::Constant(Constant::Bool(false)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_lower_sized_integers() {
+        let funcs = lower("function f(uint8 a, int24 b, uint c) returns (uint) { return a + c; }");
+        let func = &funcs[0];
+
+        assert_eq!(func.params[0].1, IRType::Uint(8));
+        assert_eq!(func.params[1].1, IRType::Int(24));
+        assert_eq!(func.params[2].1, IRType::Uint(256));
+        // uint8 先拓宽为 uint256 再参与运算
+        assert_eq!(
+            func.blocks[0].instructions[0],
+            Instruction::Extend(Value::Temporary(0), Value::Local("a".to_string()))
+        );
+        assert_eq!(func.temporaries, vec![IRType::Uint(256), IRType::Uint(256)]);
+    }
+
+    #[test]
+    fn test_lower_rejects_invalid_conversions() {
+        assert!(lower_err("function f() { uint8 x = 256; }").to_string().contains("does not fit"));
+        assert!(lower_err("function f(uint a) { uint8 x = a; }").to_string().contains("Cannot implicitly convert"));
+        assert!(lower_err("function f(uint8 a) { int8 x = a; }").to_string().contains("Cannot implicitly convert"));
+        assert_eq!(
+            lower_err("function f(mapping(address => uint) m) { }").to_string(),
+            "Unsupported type: mapping(address => uint256)"
+        );
+        assert_eq!(lower_err("function f() { bytes32[] x; }").to_string(), "Unsupported type: bytes32[]");
+    }
+
+    #[test]
+    fn test_lower_if_else() {
+        let funcs = lower(
+            "function f(uint a, uint b) returns (uint) {
+                uint m = b;
+                if (a > b) { m = a; }
+                if (m < 10) { return 10; } else if (m > 100) { return 100; } else { m = m + 1; }
+                return m;
+            }",
+        );
+        let func = &funcs[0];
+        let labels: Vec<_> = func.blocks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["entry", "bb1", "bb2", "bb3", "bb4", "bb7", "bb8", "bb9", "bb5"]);
+
+        let cond = |i: usize| match &func.blocks[i].terminator {
+            Terminator::Branch(cond, then_label, else_label) => {
+                assert_eq!(func.value_type(cond), Some(IRType::Bool));
+                (then_label.as_str(), else_label.as_str())
+            }
+            other => panic!("unexpected terminator: {:?}", other),
+        };
+        // 没有 else 的 if 直接跳到汇合块
+        assert_eq!(cond(0), ("bb1", "bb2"));
+        assert_eq!(func.blocks[1].terminator, Terminator::Jump("bb2".to_string()));
+        assert_eq!(cond(2), ("bb3", "bb4"));
+        assert!(matches!(func.blocks[3].terminator, Terminator::Return(_)));
+        // else if 嵌套在 else 分支中，其汇合块再跳回外层的汇合块
+        // return 之后的空块 bb6 被丢弃
+        assert_eq!(cond(4), ("bb7", "bb8"));
+        assert!(matches!(func.blocks[5].terminator, Terminator::Return(_)));
+        assert_eq!(func.blocks[6].terminator, Terminator::Jump("bb9".to_string()));
+        assert_eq!(func.blocks[7].terminator, Terminator::Jump("bb5".to_string()));
+        assert!(matches!(func.blocks[8].terminator, Terminator::Return(_)));
+    }
+
+    #[test]
+    fn test_lower_if_without_fallthrough() {
+        // 两个分支都返回时不需要隐式返回
+        let funcs = lower("function f(bool c) returns (uint) { if (c) { return 1; } else { return 2; } }");
+        assert_eq!(funcs[0].blocks.len(), 3);
+        assert!(funcs[0]
+            .blocks
+            .iter()
+            .all(|b| !matches!(&b.terminator, Terminator::Return(values) if values[0] == Value::int(0))));
+    }
+
+    #[test]
+    fn test_lower_loops() {
+        let funcs = lower(
+            "function f(uint n) returns (uint) {
+                uint s = 0;
+                for (uint i = 0; i < n; i = i + 1) {
+                    while (s > i) { if (s > 100) break; s = s - 1; }
+                    if (i > 5) continue;
+                    s = s + i;
+                }
+                do { s = s + 1; } while (s < 10);
+                return s;
+            }",
+        );
+        let func = &funcs[0];
+        let block = |label: &str| {
+            func.blocks
+                .iter()
+                .find(|b| b.label == label)
+                .unwrap_or_else(|| panic!("missing block {}", label))
+        };
+        let jump = |label: &str| Terminator::Jump(label.to_string());
+
+        // for: bb1 头部，bb2 循环体，bb3 更新，bb4 出口
+        assert_eq!(block("entry").terminator, jump("bb1"));
+        assert!(matches!(&block("bb1").terminator, Terminator::Branch(_, t, f) if t == "bb2" && f == "bb4"));
+        assert_eq!(block("bb3").terminator, jump("bb1"));
+        assert!(matches!(block("bb3").instructions[..], [Instruction::Add(..), Instruction::Move(..)]));
+        // 内层 while：bb5 头部，bb6 循环体，bb7 出口；break 跳到内层的出口
+        assert_eq!(block("bb2").terminator, jump("bb5"));
+        assert!(matches!(&block("bb5").terminator, Terminator::Branch(_, t, f) if t == "bb6" && f == "bb7"));
+        assert_eq!(block("bb8").terminator, jump("bb7"));
+        // continue 跳到外层的更新块
+        assert_eq!(block("bb11").terminator, jump("bb3"));
+        // do-while：循环体先执行，条件在回边所在的块中
+        assert_eq!(block("bb4").terminator, jump("bb14"));
+        assert!(matches!(&block("bb15").terminator, Terminator::Branch(_, t, f) if t == "bb14" && f == "bb16"));
+        assert!(matches!(block("bb16").terminator, Terminator::Return(_)));
+    }
+
+    #[test]
+    fn test_lower_rejects_misplaced_loop_control() {
+        assert!(lower_err("function f() { break; }").to_string().contains("'break' outside of a loop"));
+        assert!(lower_err("function f(bool c) { if (c) { continue; } }").to_string().contains("'continue' outside of a loop"));
+        assert!(lower_err("function f(uint a) { while (a) { } }").to_string().contains("Condition must be of type bool"));
+    }
+
+    #[test]
+    fn test_lower_short_circuit() {
+        let funcs = lower("function f(uint a, bool b) returns (bool) { return a > 1 && b || a == 0; }");
+        let func = &funcs[0];
+        let labels: Vec<_> = func.blocks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["entry", "bb1", "bb2", "bb3", "bb4"]);
+
+        // a > 1 为假时跳过 b，&& 的结果为假时才计算 a == 0
+        let and = Value::Local("%bb2".to_string());
+        let or = Value::Local("%bb4".to_string());
+        assert!(matches!(&func.blocks[0].terminator, Terminator::Branch(_, t, f) if t == "bb1" && f == "bb2"));
+        assert_eq!(func.blocks[1].instructions, vec![Instruction::Move(and.clone(), Value::Local("b".to_string()))]);
+        assert_eq!(func.blocks[2].instructions, vec![Instruction::Move(or.clone(), and.clone())]);
+        assert_eq!(func.blocks[2].terminator, Terminator::Branch(and, "bb4".to_string(), "bb3".to_string()));
+        assert!(matches!(func.blocks[3].instructions[..], [Instruction::Eq(..), Instruction::Move(..)]));
+        assert_eq!(func.blocks[4].terminator, Terminator::Return(vec![or]));
+        assert_eq!(func.locals.get("%bb4"), Some(&IRType::Bool));
+    }
+
+    #[test]
+    fn test_lower_rejects_non_bool_logical_operands() {
+        let tokens: Vec<_> = Lexer::new("function f(uint a, bool b) returns (bool) { return b && a; }").collect();
+        let unit = Parser::new(tokens).parse().unwrap();
+        assert!(lower_function(&unit.functions[0]).unwrap_err().to_string().contains("Operand of && must be of type bool"));
+    }
+
+    #[test]
+    fn test_lower_increment_evaluates_target_once() {
+        let funcs = lower_contract_source(
+            "contract C {
+                mapping(uint => uint8) m;
+                function f(uint k) returns (uint8) { m[k] += 2; return m[k]++; }
+            }",
+        )
+        .unwrap();
+        let instructions = &funcs[0].blocks[0].instructions;
+
+        // 每条语句各求一次映射槽号，读出、修改、写回都使用它
+        let slots: Vec<_> = instructions
+            .iter()
+            .filter_map(|inst| match inst {
+                Instruction::MappingSlot(dest, _, _) => Some(dest.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(slots.len(), 2);
+        assert!(matches!(&instructions[1..4], [
+            Instruction::StorageLoad(old, s1, 0),
+            Instruction::Add(new, old2, two),
+            Instruction::StorageStore(s2, 0, new2),
+        ] if *two == Value::int(2) && *s1 == slots[0] && *s2 == slots[0] && old == old2 && new == new2));
+
+        // 后缀形式返回旧值
+        let [.., Instruction::StorageLoad(old, _, 0), Instruction::Add(_, old2, _), Instruction::StorageStore(..)] =
+            &instructions[..]
+        else {
+            panic!("unexpected instructions: {:?}", instructions);
+        };
+        assert_eq!(old, old2);
+        assert_eq!(funcs[0].blocks[0].terminator, Terminator::Return(vec![old.clone()]));
+    }
+
+    #[test]
+    fn test_lower_unary_operators() {
+        let funcs = lower(
+            "function f(int16 a, bool b, uint c) returns (int16, bool, uint, int16) {
+                int16 x = a++;
+                x -= -1;
+                return (-a, !b, ~c, x);
+            }",
+        );
+        let func = &funcs[0];
+        let instructions = &func.blocks[0].instructions;
+
+        // 后缀自增先保存局部变量的旧值
+        let a = Value::Local("a".to_string());
+        assert!(matches!(&instructions[..3], [
+            Instruction::Move(saved, src),
+            Instruction::Add(new, _, _),
+            Instruction::Move(dst, new2),
+        ] if *src == a && *dst == a && new == new2 && matches!(saved, Value::Temporary(_))));
+        assert!(matches!(&instructions[3], Instruction::Move(x, saved) if *x == Value::Local("x".to_string())
+            && *saved == Value::Temporary(0)));
+        assert!(matches!(&instructions[4], Instruction::Sub(_, _, one) if *one == Value::int(-1)));
+        assert!(matches!(&instructions[6], Instruction::Sub(_, zero, _) if *zero == Value::int(0)));
+        assert!(matches!(&instructions[7], Instruction::Eq(_, _, Value::Constant(Constant::Bool(false)))));
+        assert!(matches!(&instructions[8], Instruction::Not(dest, _) if func.value_type(dest) == Some(IRType::Uint(256))));
+    }
+
+    #[test]
+    fn test_lower_rejects_invalid_unary_operands() {
+        assert!(lower_err("function f(uint a) returns (uint) { return -a; }").to_string().contains("Unary minus"));
+        assert!(lower_err("function f(uint a) returns (bool) { return !a; }").to_string().contains("Operand of !"));
+        assert!(lower_err("function f(bool b) { b++; }").to_string().contains("requires an integer operand"));
+        assert!(lower_err("function f(uint a) { a + 1 += 2; }").to_string().contains("not assignable"));
+        assert!(lower_err("function f(uint8 a, uint b) { a += b; }").to_string().contains("Cannot implicitly convert"));
+    }
+
+    #[test]
+    fn test_lower_folds_literal_expressions() {
+        let funcs = lower("function f() returns (int, bool) { return (-2 ** 3 + 7 % 3 - (1 << 4 | 5 ^ 3), 2 ** 10 > 1000); }");
+        assert_eq!(
+            funcs[0].blocks[0].terminator,
+            Terminator::Return(vec![
+                Value::int(-8 + 1 - 22),
+                Value::Constant(Constant::Bool(true)),
+            ])
+        );
+        assert!(funcs[0].blocks[0].instructions.is_empty());
+
+        // 超过 64 位的乘方和移位得到完整的 uint256 常量
+        let funcs = lower("function f() returns (uint, uint) { return (2 ** 255, 1 << 200); }");
+        assert_eq!(
+            funcs[0].blocks[0].terminator,
+            Terminator::Return(vec![Value::int(BigInt::from(1) << 255), Value::int(BigInt::from(1) << 200)])
+        );
+        assert!(funcs[0].blocks[0].instructions.is_empty());
+
+        assert!(lower_err("function f() returns (int) { return 1 % 0; }").to_string().contains("Division by zero"));
+        assert!(lower_err("function f() returns (uint8) { return 200 + 100; }").to_string().contains("does not fit"));
+        assert!(lower_err("function f() returns (uint) { return 2 ** 256; }").to_string().contains("does not fit"));
+        assert!(lower_err("function f() returns (uint) { return -1; }").to_string().contains("does not fit"));
+        assert!(lower_err("function f() returns (uint) { return 7 / 2; }").to_string().contains("not an integer"));
+        assert!(lower_err("function f() returns (uint) { return (1 / 3) << 2; }").to_string().contains("requires integer operands"));
+        assert!(lower_err("function f() returns (uint) { return 2 ** 4097; }").to_string().contains("exceeds 4096 bits"));
+    }
+
+    #[test]
+    fn test_lower_folds_literals_as_rationals() {
+        // 中间结果按有理数计算，不会溢出或截断
+        let funcs = lower(
+            "function f() returns (uint, uint8, int8, int) {
+                return (2 ** 256 - 1, 5 / 2 * 2, -2 ** 7, 2 ** 300 / 2 ** 299 - 3);
+            }",
+        );
+        let max = (BigInt::from(1) << 256) - 1;
+        assert_eq!(
+            funcs[0].blocks[0].terminator,
+            Terminator::Return(vec![Value::int(max), Value::int(5), Value::int(-128), Value::int(-1)])
+        );
+        assert!(funcs[0].blocks[0].instructions.is_empty());
+    }
+
+    #[test]
+    fn test_lower_shift_and_power() {
+        let funcs = lower(
+            "function f(uint8 a, uint b, uint16 e) returns (uint8, uint, uint8, uint8) {
+                return (a << b, 1 << e, a ** 3, a ** e);
+            }",
+        );
+        let func = &funcs[0];
+        let entry = &func.blocks[0].instructions;
+
+        // 移位的结果类型取自左侧，右侧不做转换；字面量左侧按 uint256 处理
+        assert!(matches!(&entry[0], Instruction::Shl(dest, _, Value::Local(b))
+            if b == "b" && func.value_type(dest) == Some(IRType::Uint(8))));
+        assert!(matches!(&entry[1], Instruction::Shl(dest, one, _)
+            if *one == Value::int(1) && func.value_type(dest) == Some(IRType::Uint(256))));
+        // 字面量指数展开为 a * (a * a)
+        assert!(matches!(&entry[2..4], [Instruction::Mul(square, _, _), Instruction::Mul(_, _, rhs)] if square == rhs));
+
+        // 其他指数生成平方乘循环：entry、header、body、multiply、step、exit
+        assert_eq!(func.blocks.len(), 6);
+        assert!(func.locals.contains_key("%bb5.result") && func.locals.contains_key("%bb5.exponent"));
+        assert!(matches!(&func.blocks[0].terminator, Terminator::Jump(header) if header == "bb1"));
+        assert!(matches!(&func.blocks[4].instructions[..], [.., Instruction::Shr(dest, _, one), Instruction::Move(..)]
+            if *one == Value::int(1) && func.value_type(dest) == Some(IRType::Uint(16))));
+        assert!(matches!(&func.blocks[5].terminator, Terminator::Return(values)
+            if values[3] == Value::Local("%bb5.result".to_string())));
+    }
+
+    #[test]
+    fn test_lower_rejects_invalid_shift_operands() {
+        assert!(lower_err("function f(uint a, int8 n) returns (uint) { return a << n; }").to_string().contains("must be an unsigned integer"));
+        assert!(lower_err("function f(uint a) returns (uint) { return a ** -1; }").to_string().contains("must be an unsigned integer"));
+        assert!(lower_err("function f(bool a) returns (bool) { return a >> 1; }").to_string().contains("integer left operand"));
+        assert!(lower_err("function f(bool a, bool b) returns (bool) { return a & b; }").to_string().contains("requires integer operands"));
+    }
+
+    #[test]
+    fn test_lower_calls() {
+        let funcs = lower(
+            "function f(uint8 x) returns (uint) { log(); return g(x, 1) + g(2, x); }
+            function g(uint16 a, uint b) returns (uint) { return a + b; }
+            function log() { }",
+        );
+        let func = &funcs[0];
+        let calls: Vec<_> = func.blocks[0]
+            .instructions
+            .iter()
+            .filter_map(|inst| match inst {
+                Instruction::Call(dest, callee, args) => Some((dest, callee.as_str(), args)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls.len(), 3);
+        assert!(matches!(calls[0], (None, "log", args) if args.is_empty()));
+
+        // 实参按形参类型转换，字面量放进形参类型的临时值
+        let arg_types = |args: &[Value]| args.iter().map(|arg| func.value_type(arg)).collect::<Vec<_>>();
+        for (dest, callee, args) in &calls[1..] {
+            assert_eq!(*callee, "g");
+            assert!(dest.as_ref().is_some_and(|dest| func.value_type(dest) == Some(IRType::Uint(256))));
+            assert_eq!(arg_types(args), vec![Some(IRType::Uint(16)), Some(IRType::Uint(256))]);
+            assert!(args.iter().all(|arg| matches!(arg, Value::Temporary(_))));
+        }
+    }
+
+    #[test]
+    fn test_lower_contract_calls() {
+        let funcs = lower_contract_source(
+            "contract C {
+                uint total = 1;
+                constructor() { add(2); }
+                function add(uint amount) { total += amount; }
+                function get() returns (uint) { return total; }
+            }",
+        )
+        .unwrap();
+        assert!(funcs[0].blocks[0]
+            .instructions
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Call(None, callee, _) if callee == "C.add")));
+        // 合约中的函数以合约名作前缀，生成的构造函数也是如此
+        let names: Vec<_> = funcs.iter().map(|func| func.name.as_str()).collect();
+        assert_eq!(names, ["C.constructor", "C.add", "C.get"]);
+    }
+
+    #[test]
+    fn test_lower_address_keyed_mapping() {
+        let funcs = lower_contract_source(
+            "contract C {
+                mapping(address => uint) m;
+                function f() { address a; m[a] = 1; }
+            }",
+        )
+        .unwrap();
+        let func = &funcs[0];
+        let a = Value::Local("a".to_string());
+        assert!(matches!(&func.blocks[0].instructions[..], [
+            Instruction::Move(local, Value::Constant(Constant::Address(_))),
+            Instruction::MappingSlot(slot, key, map),
+            ..,
+            Instruction::StorageStore(slot2, 0, _),
+        ] if *local == a && *key == a && *map == Value::int(0) && slot == slot2));
+        assert_eq!(func.value_type(&a), Some(IRType::Address));
+    }
+
+    #[test]
+    fn test_lower_initializers_ignore_constructor_params() {
+        // 构造函数的参数 x 与状态变量同名，初始值仍然写入并读取状态变量 x
+        let funcs = lower_contract_source(
+            "contract C {
+                uint x = 5;
+                uint y = x + 1;
+                constructor(uint x) { }
+            }",
+        )
+        .unwrap();
+        let slot = |n: u64| Value::int(n);
+        let x = Value::Local("x".to_string());
+        let instructions = &funcs[0].blocks[0].instructions;
+        assert!(matches!(&instructions[..], [
+            Instruction::Move(five, c),
+            Instruction::StorageStore(s0, 0, five2),
+            Instruction::StorageLoad(loaded, s0b, 0),
+            Instruction::Add(sum, loaded2, _),
+            Instruction::StorageStore(s1, 0, sum2),
+        ] if *c == Value::int(5) && five == five2 && *s0 == slot(0) && *s0b == slot(0)
+            && loaded == loaded2 && *s1 == slot(1) && sum == sum2));
+        assert!(instructions.iter().all(|inst| !inst.operands().contains(&&x) && inst.dest() != Some(&x)));
+        assert_eq!(funcs[0].params, vec![("x".to_string(), IRType::Uint(256))]);
+    }
+
+    #[test]
+    fn test_lower_contracts_mangle_symbols() {
+        let lower_contracts_source = |input: &str| {
+            let tokens: Vec<_> = Lexer::new(input).collect();
+            lower_contracts(&Parser::new(tokens).parse().unwrap().contracts)
+        };
+        let funcs = lower_contracts_source(
+            "contract A { uint x = 1; function f() { g(); } function g() { } }
+            contract B { uint y = 2; function f() { } }",
+        )
+        .unwrap();
+        let names: Vec<_> = funcs.iter().map(|func| func.name.as_str()).collect();
+        assert_eq!(names, ["A.constructor", "A.f", "A.g", "B.constructor", "B.f"]);
+        assert!(matches!(&funcs[1].blocks[0].instructions[..], [Instruction::Call(None, callee, _)] if callee == "A.g"));
+
+        let error = lower_contracts_source("contract A { }\ncontract A { }").unwrap_err();
+        assert!(matches!(error, SemaError::DuplicateDeclaration(_)));
+        let diagnostic = error.diagnostic();
+        assert_eq!(diagnostic.message, "Contract A is declared more than once");
+        assert_eq!(diagnostic.labels.len(), 2);
+    }
+
+    #[test]
+    fn test_lower_rejects_invalid_calls() {
+        assert!(lower_err("function f() { g(); }").to_string().contains("Undeclared function: g"));
+        assert!(lower_err("function f(uint a) { a(); }").to_string().contains("a is not a function"));
+        assert!(lower_err("function f() { f(1); }").to_string().contains("expects 0 arguments, but 1 were given"));
+        assert!(lower_err("function f() returns (uint) { return g(); } function g() { }").to_string().contains("does not return a value"));
+        assert!(lower_err("function f(int8 a) { g(a); } function g(uint a) { }").to_string().contains("Cannot implicitly convert"));
+        assert!(lower_err("function f() { g(); } function g() returns (uint, uint) { return (1, 2); }").to_string().contains("multiple return values"));
+        assert!(lower_err("function f() { } function f(uint a) { }").to_string().contains("overloading is not supported"));
+    }
+
+    #[test]
+    fn test_lower_nested_block() {
+        let funcs = lower("function f() returns (uint) { uint a = 1; { a = 2; { } } return a; }");
+        let a = Value::Local("a".to_string());
+        assert_eq!(
+            funcs[0].blocks[0].instructions,
+            vec![Instruction::Move(a.clone(), Value::int(1)), Instruction::Move(a.clone(), Value::int(2))]
+        );
+        assert_eq!(funcs[0].blocks[0].terminator, Terminator::Return(vec![a]));
+    }
+
+    #[test]
+    fn test_lower_block_scopes() {
+        // 内层的 a 遮蔽参数，使用自己的类型；相邻代码块中的同名变量互不影响
+        let funcs = lower(
+            "function f(uint8 a) returns (uint) {
+                { uint a = 300; a += 1; }
+                { bool a = true; }
+                for (uint i = 0; i < 2; i++) { uint8 i = 1; }
+                return a;
+            }",
+        );
+        let func = &funcs[0];
+        let local = |name: &str| Value::Local(name.to_string());
+        assert_eq!(func.value_type(&local("a")), Some(IRType::Uint(8)));
+        assert_eq!(func.value_type(&local("a.1")), Some(IRType::Uint(256)));
+        assert_eq!(func.value_type(&local("a.2")), Some(IRType::Bool));
+        assert_eq!(func.value_type(&local("i.1")), Some(IRType::Uint(8)));
+        assert!(matches!(&func.blocks[0].instructions[..2], [
+            Instruction::Move(a, _),
+            Instruction::Add(_, a2, _),
+        ] if *a == local("a.1") && *a2 == local("a.1")));
+        // 返回的是 uint8 参数，拓宽为 uint256
+        let exit = func.blocks.iter().find(|block| matches!(block.terminator, Terminator::Return(_))).unwrap();
+        assert!(matches!(&exit.instructions[..], [Instruction::Extend(_, src)] if *src == local("a")));
+    }
+
+    #[test]
+    fn test_lower_rejects_redeclaration() {
+        let error = lower_err("function f() {\n    uint a;\n    { uint b; }\n    bool a;\n}");
+        assert!(matches!(error, SemaError::DuplicateDeclaration(_)));
+        let diagnostic = error.diagnostic();
+        assert_eq!(diagnostic.message, "Variable a is already declared in this scope");
+        assert!(matches!(&diagnostic.labels[..], [redeclared, first]
+            if redeclared.primary && redeclared.span.line == 4 && !first.primary && first.span.line == 2));
+
+        // 参数与函数体最外层在同一个作用域中
+        assert!(matches!(lower_err("function f(uint a) { uint8 a; }"), SemaError::DuplicateDeclaration(_)));
+        assert!(matches!(lower_err("function f(uint a, bool a) { }"), SemaError::DuplicateDeclaration(_)));
+        // 离开代码块或循环后，其中声明的变量不再可见
+        assert!(matches!(lower_err("function f() { { uint x; } x = 1; }"), SemaError::UndeclaredIdentifier(_)));
+        assert!(matches!(
+            lower_err("function f() { for (uint i = 0; i < 2; i++) { } i = 1; }"),
+            SemaError::UndeclaredIdentifier(_)
+        ));
+    }
+
+    #[test]
+    fn test_lower_rejects_non_bool_condition() {
+        let tokens: Vec<_> = Lexer::new("function f(uint a) { if (a) { a = 1; } }").collect();
+        let unit = Parser::new(tokens).parse().unwrap();
+        assert!(lower_function(&unit.functions[0]).unwrap_err().to_string().contains("Condition must be of type bool"));
+    }
+
+    #[test]
+    fn test_lower_undeclared_variable() {
+        let tokens: Vec<_> = Lexer::new("function f() returns (uint) { return y; }").collect();
+        let functions = Parser::new(tokens).parse().unwrap().functions;
+        assert!(lower_function(&functions[0]).is_err());
+    }
+
+    #[test]
+    fn test_lower_state_variables() {
+        let funcs = lower_contract_source(
+            "contract C {
+                uint8 a = 1; bool b; mapping(address => uint) balances;
+                function f(address who) returns (uint8) { b = true; balances[who] = 2; return a; }
+            }",
+        )
+        .unwrap();
+        let slot = |n| Value::int(n);
+
+        // 状态变量的初始值在生成的构造函数中写入
+        assert_eq!(funcs[0].name, "C.constructor");
+        assert_eq!(
+            funcs[0].blocks[0].instructions,
+            vec![
+                Instruction::Move(Value::Temporary(0), slot(1)),
+                Instruction::StorageStore(slot(0), 0, Value::Temporary(0)),
+            ]
+        );
+        assert_eq!(funcs[0].temporaries, vec![IRType::Uint(8)]);
+
+        let f = &funcs[1];
+        assert_eq!(
+            f.blocks[0].instructions,
+            vec![
+                Instruction::Move(Value::Temporary(0), Value::Constant(Constant::Bool(true))),
+                Instruction::StorageStore(slot(0), 1, Value::Temporary(0)),
+                Instruction::MappingSlot(Value::Temporary(1), Value::Local("who".to_string()), slot(1)),
+                Instruction::Move(Value::Temporary(2), Value::int(2)),
+                Instruction::StorageStore(Value::Temporary(1), 0, Value::Temporary(2)),
+                Instruction::StorageLoad(Value::Temporary(3), slot(0), 0),
+            ]
+        );
+        assert_eq!(f.temporaries, vec![IRType::Bool, IRType::Uint(256), IRType::Uint(256), IRType::Uint(8)]);
+        assert_eq!(f.blocks[0].terminator, Terminator::Return(vec![Value::Temporary(3)]));
+    }
+
+    #[test]
+    fn test_lower_rejects_invalid_storage_access() {
+        let source = |body: &str| format!("contract C {{ uint a; mapping(uint => bool) m; function f() {{ {} }} }}", body);
+
+        assert!(lower_contract_source(&source("a[1] = 2;")).unwrap_err().message.contains("Indexing"));
+        assert!(lower_contract_source(&source("m = 2;")).unwrap_err().message.contains("cannot be used as a value"));
+        assert!(lower_contract_source(&source("m[1] = a;")).unwrap_err().message.contains("Cannot implicitly convert"));
+        assert!(lower_contract_source(&source("c = 2;")).unwrap_err().message.contains("Undeclared variable"));
+    }
+
+    #[test]
+    fn test_lower_error_locations() {
+        let span_of = |input: &str| {
+            let tokens: Vec<_> = Lexer::new(input).collect();
+            let functions = Parser::new(tokens).parse().unwrap().functions;
+            let error = Diagnostic::from(lower_functions(&functions).unwrap_err());
+            let span = error.span().unwrap();
+            (error.code, input[span.start..span.end].to_string())
+        };
+
+        // 错误指向最内层的相关节点
+        assert_eq!(
+            span_of("function f(bool b) returns (uint) { if (b) { return 1 + b * 2; } }"),
+            (codes::TYPE_MISMATCH, "b * 2".to_string())
+        );
+        assert_eq!(
+            span_of("function f(uint a) { while (a) { } }"),
+            (codes::TYPE_MISMATCH, "a".to_string())
+        );
+        assert_eq!(span_of("function f() { x = 1; }"), (codes::UNDECLARED_IDENTIFIER, "x".to_string()));
+        assert_eq!(
+            span_of("function f() { uint8 x = 1; if (true) { break; } }"),
+            (codes::INVALID_CONTROL_FLOW, "break;".to_string())
+        );
+        assert_eq!(
+            span_of("function f() { g(1, 2); } function g(uint a) { }"),
+            (codes::WRONG_ARGUMENT_COUNT, "g(1, 2)".to_string())
+        );
+        assert_eq!(
+            span_of("function f() { } function f() { }"),
+            (codes::DUPLICATE_DECLARATION, "function f() { }".to_string())
+        );
+    }
+}
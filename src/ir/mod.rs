@@ -1,10 +1,18 @@
 use std::collections::HashMap;
 
+use num_bigint::BigInt;
+
+pub mod cfg;
+pub mod literal;
+pub mod lower;
+pub mod ssa;
+pub mod storage;
+
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum IRType {
-    I32,
-    I64,
+    Uint(u16), // 整数的位宽，8 到 256 之间的 8 的倍数
+    Int(u16),
     Bool,
     Address,
     String,
@@ -16,7 +24,8 @@ pub struct IRFunction {
     pub name: String,
     pub params: Vec<(String, IRType)>,
     pub returns: Vec<IRType>,
-    pub locals: HashMap<String, IRType>,
+    pub locals: HashMap<String, IRType>, // 与参数不重名，遮蔽外层变量的局部变量另取名字
+    pub temporaries: Vec<IRType>, // 按编号索引的临时值类型
     pub blocks: Vec<BasicBlock>,
 }
 
@@ -28,43 +37,210 @@ pub struct BasicBlock {
     pub terminator: Terminator,
 }
 
+/// 三地址形式的 IR 指令。
+///
+/// 每条产生值的指令都把结果写入第一个操作数（目标值），目标值通常是由
+/// [`IRFunction::new_temp`] 分配的 `Value::Temporary`；`Move` 还可以把值写入局部变量。
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Instruction {
+    // 数据传送
+    Move(Value, Value), // dest, src
+
     // 算术运算
-    Add(Value, Value),
-    Sub(Value, Value),
-    Mul(Value, Value),
-    Div(Value, Value),
-    
+    Add(Value, Value, Value), // dest, lhs, rhs
+    Sub(Value, Value, Value),
+    Mul(Value, Value, Value),
+    Div(Value, Value, Value),
+    Mod(Value, Value, Value),
+
+    // 位运算
+    Not(Value, Value), // dest, src：按位取反
+    And(Value, Value, Value), // dest, lhs, rhs
+    Or(Value, Value, Value),
+    Xor(Value, Value, Value),
+    // 移位的结果类型与 lhs 相同，rhs 是任意无符号整数；rhs 不小于位宽时结果为 0，
+    // 有符号数右移则为符号位填充（算术右移）
+    Shl(Value, Value, Value),
+    Shr(Value, Value, Value),
+
     // 比较运算
-    Eq(Value, Value),
-    Ne(Value, Value),
-    Lt(Value, Value),
-    Gt(Value, Value),
-    
+    Eq(Value, Value, Value), // dest, lhs, rhs
+    Ne(Value, Value, Value),
+    Lt(Value, Value, Value),
+    Gt(Value, Value, Value),
+    Le(Value, Value, Value),
+    Ge(Value, Value, Value),
+
     // 内存操作
-    Load(Value),
-    Store(Value, Value),
-    
+    Load(Value, Value),  // dest, address
+    Store(Value, Value), // address, value
+
+    // 合约存储。槽号是 256 位整数，偏移是变量在槽内从低位算起的字节偏移，
+    // 读写的字节数由 dest 或 value 的类型决定（见 [`storage::storage_bytes`]）
+    StorageLoad(Value, Value, u8),    // dest, slot, offset
+    StorageStore(Value, u8, Value),   // slot, offset, value
+    MappingSlot(Value, Value, Value), // dest, key, mapping_slot：映射元素的槽号 keccak256(key . mapping_slot)
+
     // 函数调用
-    Call(String, Vec<Value>),
-    
-    // 类型转换
-    Trunc(Value),
-    Extend(Value),
+    Call(Option<Value>, String, Vec<Value>), // dest, callee, args
+
+    // 类型转换（目标宽度由 dest 的类型决定）
+    Trunc(Value, Value),  // dest, src
+    Extend(Value, Value), // dest, src
+
+    // SSA 合并节点，只能出现在基本块开头
+    Phi(Value, Vec<(String, Value)>), // dest, [(predecessor_label, value)]
+}
+
+impl Instruction {
+    /// 指令定义的目标值。`Store`、`StorageStore` 和无返回值的 `Call` 不定义任何值。
+    pub fn dest(&self) -> Option<&Value> {
+        match self {
+            Instruction::Move(dest, _)
+            | Instruction::Add(dest, _, _)
+            | Instruction::Sub(dest, _, _)
+            | Instruction::Mul(dest, _, _)
+            | Instruction::Div(dest, _, _)
+            | Instruction::Mod(dest, _, _)
+            | Instruction::Not(dest, _)
+            | Instruction::And(dest, _, _)
+            | Instruction::Or(dest, _, _)
+            | Instruction::Xor(dest, _, _)
+            | Instruction::Shl(dest, _, _)
+            | Instruction::Shr(dest, _, _)
+            | Instruction::Eq(dest, _, _)
+            | Instruction::Ne(dest, _, _)
+            | Instruction::Lt(dest, _, _)
+            | Instruction::Gt(dest, _, _)
+            | Instruction::Le(dest, _, _)
+            | Instruction::Ge(dest, _, _)
+            | Instruction::Load(dest, _)
+            | Instruction::StorageLoad(dest, _, _)
+            | Instruction::MappingSlot(dest, _, _)
+            | Instruction::Trunc(dest, _)
+            | Instruction::Extend(dest, _)
+            | Instruction::Phi(dest, _) => Some(dest),
+            Instruction::Call(dest, _, _) => dest.as_ref(),
+            Instruction::Store(_, _) | Instruction::StorageStore(_, _, _) => None,
+        }
+    }
+
+    /// 指令读取的操作数（不含目标值）。
+    pub fn operands(&self) -> Vec<&Value> {
+        match self {
+            Instruction::Move(_, src)
+            | Instruction::Load(_, src)
+            | Instruction::StorageLoad(_, src, _)
+            | Instruction::Not(_, src)
+            | Instruction::Trunc(_, src)
+            | Instruction::Extend(_, src) => vec![src],
+            Instruction::Add(_, lhs, rhs)
+            | Instruction::Sub(_, lhs, rhs)
+            | Instruction::Mul(_, lhs, rhs)
+            | Instruction::Div(_, lhs, rhs)
+            | Instruction::Mod(_, lhs, rhs)
+            | Instruction::And(_, lhs, rhs)
+            | Instruction::Or(_, lhs, rhs)
+            | Instruction::Xor(_, lhs, rhs)
+            | Instruction::Shl(_, lhs, rhs)
+            | Instruction::Shr(_, lhs, rhs)
+            | Instruction::Eq(_, lhs, rhs)
+            | Instruction::Ne(_, lhs, rhs)
+            | Instruction::Lt(_, lhs, rhs)
+            | Instruction::Gt(_, lhs, rhs)
+            | Instruction::Le(_, lhs, rhs)
+            | Instruction::Ge(_, lhs, rhs)
+            | Instruction::MappingSlot(_, lhs, rhs) => vec![lhs, rhs],
+            Instruction::Store(address, value) | Instruction::StorageStore(address, _, value) => {
+                vec![address, value]
+            }
+            Instruction::Call(_, _, args) => args.iter().collect(),
+            Instruction::Phi(_, incoming) => incoming.iter().map(|(_, value)| value).collect(),
+        }
+    }
+
+    /// 与 [`Instruction::operands`] 相同，但返回可变引用，供改写操作数的分析使用。
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Instruction::Move(_, src)
+            | Instruction::Load(_, src)
+            | Instruction::StorageLoad(_, src, _)
+            | Instruction::Not(_, src)
+            | Instruction::Trunc(_, src)
+            | Instruction::Extend(_, src) => vec![src],
+            Instruction::Add(_, lhs, rhs)
+            | Instruction::Sub(_, lhs, rhs)
+            | Instruction::Mul(_, lhs, rhs)
+            | Instruction::Div(_, lhs, rhs)
+            | Instruction::Mod(_, lhs, rhs)
+            | Instruction::And(_, lhs, rhs)
+            | Instruction::Or(_, lhs, rhs)
+            | Instruction::Xor(_, lhs, rhs)
+            | Instruction::Shl(_, lhs, rhs)
+            | Instruction::Shr(_, lhs, rhs)
+            | Instruction::Eq(_, lhs, rhs)
+            | Instruction::Ne(_, lhs, rhs)
+            | Instruction::Lt(_, lhs, rhs)
+            | Instruction::Gt(_, lhs, rhs)
+            | Instruction::Le(_, lhs, rhs)
+            | Instruction::Ge(_, lhs, rhs)
+            | Instruction::MappingSlot(_, lhs, rhs) => vec![lhs, rhs],
+            Instruction::Store(address, value) | Instruction::StorageStore(address, _, value) => {
+                vec![address, value]
+            }
+            Instruction::Call(_, _, args) => args.iter_mut().collect(),
+            Instruction::Phi(_, incoming) => incoming.iter_mut().map(|(_, value)| value).collect(),
+        }
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Terminator {
-    Return(Option<Value>),
+    Return(Vec<Value>), // 返回值，可以为空或有多个
     Branch(Value, String, String), // condition, true_label, false_label
     Jump(String),
 }
 
+impl Terminator {
+    /// 后继基本块的标签。
+    pub fn successors(&self) -> Vec<&String> {
+        match self {
+            Terminator::Return(_) => Vec::new(),
+            Terminator::Branch(_, true_label, false_label) => vec![true_label, false_label],
+            Terminator::Jump(label) => vec![label],
+        }
+    }
+
+    pub fn successors_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Terminator::Return(_) => Vec::new(),
+            Terminator::Branch(_, true_label, false_label) => vec![true_label, false_label],
+            Terminator::Jump(label) => vec![label],
+        }
+    }
+
+    /// 终结指令读取的值。
+    pub fn operands(&self) -> Vec<&Value> {
+        match self {
+            Terminator::Return(values) => values.iter().collect(),
+            Terminator::Branch(cond, _, _) => vec![cond],
+            Terminator::Jump(_) => Vec::new(),
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Terminator::Return(values) => values.iter_mut().collect(),
+            Terminator::Branch(cond, _, _) => vec![cond],
+            Terminator::Jump(_) => Vec::new(),
+        }
+    }
+}
+
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Value {
     Constant(Constant),
     Local(String),
@@ -72,15 +248,50 @@ pub enum Value {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Constant {
-    I32(i32),
-    I64(i64),
+    Int(BigInt), // 整数的数值，写入前已按目标类型检查过范围
     Bool(bool),
     Address(String),
     String(String),
 }
 
+impl IRType {
+    /// 给定位宽和符号的整数类型。
+    pub fn integer(bits: u16, signed: bool) -> IRType {
+        if signed {
+            IRType::Int(bits)
+        } else {
+            IRType::Uint(bits)
+        }
+    }
+
+    /// 整数类型的位宽，其他类型返回 `None`。
+    pub fn integer_bits(&self) -> Option<u16> {
+        match self {
+            IRType::Int(bits) | IRType::Uint(bits) => Some(*bits),
+            IRType::Bool | IRType::Address | IRType::String => None,
+        }
+    }
+
+    /// 整数 `value` 能否用该类型表示。非整数类型不做检查。
+    pub fn can_represent(&self, value: &BigInt) -> bool {
+        match self {
+            IRType::Uint(bits) => value.sign() != num_bigint::Sign::Minus && value.bits() <= u64::from(*bits),
+            IRType::Int(bits) => {
+                let limit = BigInt::from(1) << (bits - 1);
+                -&limit <= *value && *value < limit
+            }
+            _ => true,
+        }
+    }
+
+    /// 是否为有符号整数类型，决定比较、除法等运算使用有符号还是无符号指令。
+    pub fn is_signed(&self) -> bool {
+        matches!(self, IRType::Int(_))
+    }
+}
+
 impl IRFunction {
     pub fn new(name: String) -> Self {
         Self {
@@ -88,6 +299,7 @@ impl IRFunction {
             params: Vec::new(),
             returns: Vec::new(),
             locals: HashMap::new(),
+            temporaries: Vec::new(),
             blocks: Vec::new(),
         }
     }
@@ -101,4 +313,70 @@ impl IRFunction {
     pub fn add_local(&mut self, name: String, ty: IRType) {
         self.locals.insert(name, ty);
     }
+
+    /// 分配一个指定类型的新临时值。
+    pub fn new_temp(&mut self, ty: IRType) -> Value {
+        let id = self.temporaries.len() as u32;
+        self.temporaries.push(ty);
+        Value::Temporary(id)
+    }
+
+    /// 分配一个新临时值，并返回把 `op(dest, lhs, rhs)` 写入该值的指令。
+    pub fn build_binary(
+        &mut self,
+        op: fn(Value, Value, Value) -> Instruction,
+        ty: IRType,
+        lhs: Value,
+        rhs: Value,
+    ) -> (Value, Instruction) {
+        let dest = self.new_temp(ty);
+        (dest.clone(), op(dest, lhs, rhs))
+    }
+
+    /// 查询值的类型：参数、局部变量、临时值或常量。
+    pub fn value_type(&self, value: &Value) -> Option<IRType> {
+        match value {
+            Value::Constant(c) => Some(c.ir_type()),
+            Value::Temporary(id) => self.temporaries.get(*id as usize).cloned(),
+            Value::Local(name) => self
+                .params
+                .iter()
+                .find(|(param, _)| param == name)
+                .map(|(_, ty)| ty.clone())
+                .or_else(|| self.locals.get(name).cloned()),
+        }
+    }
+}
+
+impl Value {
+    /// 整数常量。
+    pub fn int(n: impl Into<BigInt>) -> Value {
+        Value::Constant(Constant::Int(n.into()))
+    }
+}
+
+impl Constant {
+    /// 给定类型的零值，用于未显式初始化的变量。
+    pub fn zero(ty: &IRType) -> Option<Constant> {
+        match ty {
+            IRType::Uint(_) | IRType::Int(_) => Some(Constant::Int(BigInt::from(0))),
+            IRType::Bool => Some(Constant::Bool(false)),
+            IRType::Address => Some(Constant::Address("0x0".to_string())),
+            IRType::String => None,
+        }
+    }
+
+    /// 常量自身的类型。整数常量取能容纳它的 64 位或 256 位整数，
+    /// 这样它不会让 64 位以内的运算被当作 256 位运算。
+    pub fn ir_type(&self) -> IRType {
+        match self {
+            Constant::Int(n) => [IRType::Int(64), IRType::Uint(64), IRType::Uint(256)]
+                .into_iter()
+                .find(|ty| ty.can_represent(n))
+                .unwrap_or(IRType::Int(256)),
+            Constant::Bool(_) => IRType::Bool,
+            Constant::Address(_) => IRType::Address,
+            Constant::String(_) => IRType::String,
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,481 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::cfg::{self, Cfg, Dominators};
+use crate::ir::{BasicBlock, Constant, IRFunction, Instruction, Terminator, Value};
+
+/// 把函数转换为 SSA 形式（mem2reg 风格）。
+///
+/// 参数和局部变量通过 `Move(Local, _)` 赋值，可能在多个基本块中被多次赋值。
+/// 本过程在支配边界上插入 `Phi`，再沿支配树重命名，使每个值只有一个定义：
+/// 对局部变量的赋值被删除，读取改为使用当时到达的定义。参数的初始值仍以
+/// `Value::Local` 表示，被提升的局部变量会从 `IRFunction::locals` 中移除。
+pub fn construct(func: &mut IRFunction) {
+    cfg::remove_unreachable_blocks(func);
+    if func.blocks.is_empty() {
+        return;
+    }
+
+    let cfg = Cfg::new(func);
+    let doms = Dominators::new(&cfg);
+
+    // 每个变量在进入函数时的值
+    let mut initial: HashMap<String, Value> = HashMap::new();
+    for (name, _) in &func.params {
+        initial.insert(name.clone(), Value::Local(name.clone()));
+    }
+    let mut names: Vec<_> = func.locals.keys().cloned().collect();
+    names.sort();
+    for name in names {
+        if let Some(zero) = func.locals.get(&name).and_then(Constant::zero) {
+            initial.insert(name, Value::Constant(zero));
+        }
+    }
+
+    let phi_vars = insert_phis(func, &doms, &initial);
+
+    let mut renamer = Renamer {
+        cfg: &cfg,
+        doms: &doms,
+        phi_vars: &phi_vars,
+        stacks: initial.into_iter().map(|(name, value)| (name, vec![value])).collect(),
+    };
+    renamer.rename(func, 0);
+
+    func.locals.retain(|name, _| !renamer.stacks.contains_key(name));
+    simplify_phis(func);
+}
+
+/// 为每个被赋值的变量在其定义块的迭代支配边界上插入空的 `Phi`，
+/// 返回 phi 目标临时值到变量名的映射。
+fn insert_phis(
+    func: &mut IRFunction,
+    doms: &Dominators,
+    variables: &HashMap<String, Value>,
+) -> HashMap<u32, String> {
+    let mut def_blocks: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, block) in func.blocks.iter().enumerate() {
+        for inst in &block.instructions {
+            if let Instruction::Move(Value::Local(name), _) = inst {
+                if let Some((var, _)) = variables.get_key_value(name.as_str()) {
+                    let blocks = def_blocks.entry(var.as_str()).or_default();
+                    if !blocks.contains(&i) {
+                        blocks.push(i);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut vars: Vec<_> = def_blocks.into_iter().collect();
+    vars.sort();
+
+    let mut phi_vars = HashMap::new();
+    let mut new_phis: Vec<Vec<Instruction>> = vec![Vec::new(); func.blocks.len()];
+    for (var, defs) in vars {
+        let Some(ty) = func.value_type(&Value::Local(var.to_string())) else {
+            continue;
+        };
+        let mut has_phi = HashSet::new();
+        let mut worklist = defs.clone();
+        while let Some(block) = worklist.pop() {
+            for &frontier in doms.frontier(block) {
+                if !has_phi.insert(frontier) {
+                    continue;
+                }
+                let dest = func.new_temp(ty.clone());
+                if let Value::Temporary(id) = dest {
+                    phi_vars.insert(id, var.to_string());
+                }
+                new_phis[frontier].push(Instruction::Phi(dest, Vec::new()));
+                if !defs.contains(&frontier) {
+                    worklist.push(frontier);
+                }
+            }
+        }
+    }
+
+    for (block, phis) in func.blocks.iter_mut().zip(new_phis) {
+        block.instructions.splice(0..0, phis);
+    }
+    phi_vars
+}
+
+struct Renamer<'a> {
+    cfg: &'a Cfg,
+    doms: &'a Dominators,
+    phi_vars: &'a HashMap<u32, String>,
+    stacks: HashMap<String, Vec<Value>>,
+}
+
+impl Renamer<'_> {
+    fn current(&self, name: &str) -> Option<Value> {
+        self.stacks.get(name).and_then(|stack| stack.last()).cloned()
+    }
+
+    fn rewrite(&self, value: &mut Value) {
+        if let Value::Local(name) = value {
+            if let Some(current) = self.current(name) {
+                *value = current;
+            }
+        }
+    }
+
+    fn rename(&mut self, func: &mut IRFunction, block: usize) {
+        let mut pushed: Vec<String> = Vec::new();
+
+        let instructions = std::mem::take(&mut func.blocks[block].instructions);
+        let mut renamed = Vec::with_capacity(instructions.len());
+        for mut inst in instructions {
+            if let Instruction::Phi(Value::Temporary(id), _) = &inst {
+                if let Some(var) = self.phi_vars.get(id) {
+                    let dest = Value::Temporary(*id);
+                    self.push(var, dest, &mut pushed);
+                    renamed.push(inst);
+                    continue;
+                }
+            }
+
+            for operand in inst.operands_mut() {
+                self.rewrite(operand);
+            }
+            match inst {
+                Instruction::Move(Value::Local(name), src) if self.stacks.contains_key(&name) => {
+                    self.push(&name, src, &mut pushed);
+                }
+                inst => renamed.push(inst),
+            }
+        }
+        func.blocks[block].instructions = renamed;
+        for operand in func.blocks[block].terminator.operands_mut() {
+            self.rewrite(operand);
+        }
+
+        let label = func.blocks[block].label.clone();
+        for &succ in &self.cfg.succs[block] {
+            for inst in &mut func.blocks[succ].instructions {
+                let Instruction::Phi(Value::Temporary(id), incoming) = inst else {
+                    continue;
+                };
+                if let Some(value) = self.phi_vars.get(id).and_then(|var| self.current(var)) {
+                    incoming.push((label.clone(), value));
+                }
+            }
+        }
+
+        for &child in self.doms.children(block) {
+            self.rename(func, child);
+        }
+
+        for name in pushed {
+            if let Some(stack) = self.stacks.get_mut(&name) {
+                stack.pop();
+            }
+        }
+    }
+
+    fn push(&mut self, name: &str, value: Value, pushed: &mut Vec<String>) {
+        if let Some(stack) = self.stacks.get_mut(name) {
+            stack.push(value);
+            pushed.push(name.to_string());
+        }
+    }
+}
+
+/// 删除无用的 phi，并把所有输入都相同的 phi 替换为该输入。
+fn simplify_phis(func: &mut IRFunction) {
+    loop {
+        let mut uses: HashMap<Value, usize> = HashMap::new();
+        for block in &func.blocks {
+            for inst in &block.instructions {
+                for operand in inst.operands() {
+                    // phi 对自身的引用不算作使用
+                    if Some(operand) != inst.dest() || !matches!(inst, Instruction::Phi(..)) {
+                        *uses.entry(operand.clone()).or_default() += 1;
+                    }
+                }
+            }
+            for operand in block.terminator.operands() {
+                *uses.entry(operand.clone()).or_default() += 1;
+            }
+        }
+
+        let mut replacement = None;
+        let mut dead = None;
+        'search: for block in &func.blocks {
+            for inst in &block.instructions {
+                let Instruction::Phi(dest, incoming) = inst else {
+                    continue;
+                };
+                if !uses.contains_key(dest) {
+                    dead = Some(dest.clone());
+                    break 'search;
+                }
+                let mut inputs = incoming.iter().map(|(_, value)| value).filter(|v| *v != dest);
+                if let Some(first) = inputs.next() {
+                    if inputs.all(|value| value == first) {
+                        replacement = Some((dest.clone(), first.clone()));
+                        break 'search;
+                    }
+                }
+            }
+        }
+
+        let target = match (&dead, &replacement) {
+            (Some(dest), _) => dest.clone(),
+            (None, Some((dest, value))) => {
+                replace_uses(func, dest, value);
+                dest.clone()
+            }
+            (None, None) => return,
+        };
+        for block in &mut func.blocks {
+            block
+                .instructions
+                .retain(|inst| !matches!(inst, Instruction::Phi(dest, _) if *dest == target));
+        }
+    }
+}
+
+fn replace_uses(func: &mut IRFunction, from: &Value, to: &Value) {
+    for block in &mut func.blocks {
+        for inst in &mut block.instructions {
+            for operand in inst.operands_mut() {
+                if operand == from {
+                    *operand = to.clone();
+                }
+            }
+        }
+        for operand in block.terminator.operands_mut() {
+            if operand == from {
+                *operand = to.clone();
+            }
+        }
+    }
+}
+
+/// 把函数从 SSA 形式转换回来：消除 `Phi`，在前驱块末尾插入等价的复制。
+///
+/// 关键边会先被拆分，同一条边上的复制作为并行复制处理，必要时借助新的
+/// 临时值打破循环依赖。
+pub fn destruct(func: &mut IRFunction) {
+    split_critical_edges(func);
+
+    let mut copies: HashMap<String, Vec<(Value, Value)>> = HashMap::new();
+    for block in &mut func.blocks {
+        block.instructions.retain(|inst| match inst {
+            Instruction::Phi(dest, incoming) => {
+                for (pred, value) in incoming {
+                    copies
+                        .entry(pred.clone())
+                        .or_default()
+                        .push((dest.clone(), value.clone()));
+                }
+                false
+            }
+            _ => true,
+        });
+    }
+
+    for i in 0..func.blocks.len() {
+        if let Some(edge_copies) = copies.remove(&func.blocks[i].label) {
+            let moves = sequentialize(func, edge_copies);
+            func.blocks[i].instructions.extend(moves);
+        }
+    }
+}
+
+/// 拆分从多后继块指向含 phi 的多前驱块的边。
+fn split_critical_edges(func: &mut IRFunction) {
+    let cfg = Cfg::new(func);
+    let mut new_blocks = Vec::new();
+
+    for succ in 0..func.blocks.len() {
+        let has_phi = func.blocks[succ]
+            .instructions
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Phi(..)));
+        if !has_phi || cfg.preds[succ].len() < 2 {
+            continue;
+        }
+
+        for &pred in &cfg.preds[succ] {
+            if cfg.succs[pred].len() < 2 {
+                continue;
+            }
+            let pred_label = func.blocks[pred].label.clone();
+            let succ_label = func.blocks[succ].label.clone();
+            let edge_label = format!("{}_{}", pred_label, succ_label);
+
+            for label in func.blocks[pred].terminator.successors_mut() {
+                if *label == succ_label {
+                    *label = edge_label.clone();
+                }
+            }
+            for inst in &mut func.blocks[succ].instructions {
+                if let Instruction::Phi(_, incoming) = inst {
+                    for (label, _) in incoming.iter_mut() {
+                        if *label == pred_label {
+                            *label = edge_label.clone();
+                        }
+                    }
+                }
+            }
+            new_blocks.push(BasicBlock {
+                label: edge_label,
+                instructions: Vec::new(),
+                terminator: Terminator::Jump(succ_label),
+            });
+        }
+    }
+
+    func.blocks.extend(new_blocks);
+}
+
+/// 把一组并行复制 `dest <- src` 转换为等价的顺序 `Move` 序列。
+fn sequentialize(func: &mut IRFunction, copies: Vec<(Value, Value)>) -> Vec<Instruction> {
+    let mut pending: Vec<_> = copies.into_iter().filter(|(dest, src)| dest != src).collect();
+    let mut moves = Vec::new();
+
+    while !pending.is_empty() {
+        // 目标值不再被其他复制读取时可以安全写入
+        let ready = pending
+            .iter()
+            .position(|(dest, _)| !pending.iter().any(|(_, src)| src == dest));
+        match ready {
+            Some(i) => {
+                let (dest, src) = pending.remove(i);
+                moves.push(Instruction::Move(dest, src));
+            }
+            None => {
+                // 只剩循环：先把一个目标的旧值保存到新临时值中
+                let dest = pending[0].0.clone();
+                let ty = func.value_type(&dest).unwrap_or(crate::ir::IRType::Int(64));
+                let saved = func.new_temp(ty);
+                moves.push(Instruction::Move(saved.clone(), dest.clone()));
+                for (_, src) in pending.iter_mut() {
+                    if *src == dest {
+                        *src = saved.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::IRType;
+
+    fn local(name: &str) -> Value {
+        Value::Local(name.to_string())
+    }
+
+    fn int(n: i64) -> Value {
+        Value::int(n)
+    }
+
+    fn block(label: &str, instructions: Vec<Instruction>, terminator: Terminator) -> BasicBlock {
+        BasicBlock {
+            label: label.to_string(),
+            instructions,
+            terminator,
+        }
+    }
+
+    /// uint x = 1; if (c) { x = 2; } return x + 1;
+    fn diamond() -> IRFunction {
+        let mut func = IRFunction::new("f".to_string());
+        func.params.push(("c".to_string(), IRType::Bool));
+        func.add_local("x".to_string(), IRType::Int(64));
+        let sum = func.new_temp(IRType::Int(64));
+        func.add_block(block(
+            "entry",
+            vec![Instruction::Move(local("x"), int(1))],
+            Terminator::Branch(local("c"), "then".to_string(), "merge".to_string()),
+        ));
+        func.add_block(block(
+            "then",
+            vec![Instruction::Move(local("x"), int(2))],
+            Terminator::Jump("merge".to_string()),
+        ));
+        func.add_block(block(
+            "merge",
+            vec![Instruction::Add(sum.clone(), local("x"), int(1))],
+            Terminator::Return(vec![sum]),
+        ));
+        func
+    }
+
+    #[test]
+    fn test_construct_inserts_phi() {
+        let mut func = diamond();
+        construct(&mut func);
+
+        assert!(func.locals.is_empty());
+        assert!(func.blocks[0].instructions.is_empty());
+        assert!(func.blocks[1].instructions.is_empty());
+
+        let merge = &func.blocks[2].instructions;
+        let Instruction::Phi(phi, incoming) = &merge[0] else {
+            panic!("expected phi, got {:?}", merge[0]);
+        };
+        assert_eq!(
+            incoming,
+            &vec![("entry".to_string(), int(1)), ("then".to_string(), int(2))]
+        );
+        assert_eq!(merge[1], Instruction::Add(Value::Temporary(0), phi.clone(), int(1)));
+    }
+
+    #[test]
+    fn test_construct_removes_trivial_phi() {
+        let mut func = diamond();
+        func.blocks[1].instructions.clear();
+        construct(&mut func);
+
+        assert_eq!(
+            func.blocks[2].instructions,
+            vec![Instruction::Add(Value::Temporary(0), int(1), int(1))]
+        );
+    }
+
+    #[test]
+    fn test_destruct_splits_critical_edge() {
+        let mut func = diamond();
+        construct(&mut func);
+        destruct(&mut func);
+
+        let labels: Vec<_> = func.blocks.iter().map(|b| b.label.as_str()).collect();
+        assert_eq!(labels, vec!["entry", "then", "merge", "entry_merge"]);
+        assert_eq!(
+            func.blocks[0].terminator,
+            Terminator::Branch(local("c"), "then".to_string(), "entry_merge".to_string())
+        );
+        assert!(func
+            .blocks
+            .iter()
+            .all(|b| b.instructions.iter().all(|i| !matches!(i, Instruction::Phi(..)))));
+        assert_eq!(func.blocks[1].instructions, vec![Instruction::Move(Value::Temporary(1), int(2))]);
+        assert_eq!(func.blocks[3].instructions, vec![Instruction::Move(Value::Temporary(1), int(1))]);
+    }
+
+    #[test]
+    fn test_sequentialize_swap() {
+        let mut func = IRFunction::new("f".to_string());
+        let a = func.new_temp(IRType::Int(64));
+        let b = func.new_temp(IRType::Int(64));
+        let moves = sequentialize(&mut func, vec![(a.clone(), b.clone()), (b.clone(), a.clone())]);
+
+        let saved = Value::Temporary(2);
+        assert_eq!(
+            moves,
+            vec![
+                Instruction::Move(saved.clone(), a.clone()),
+                Instruction::Move(a, b.clone()),
+                Instruction::Move(b, saved),
+            ]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use crate::frontend::diagnostic::{codes, Diagnostic};
+use crate::ir::lower::SemaError;
+use crate::frontend::parser::{StateVariable, TypeName};
+use crate::ir::lower::convert_solidity_type_to_ir_type;
+use crate::ir::IRType;
+
+/// 一个存储槽的字节数。
+pub const SLOT_BYTES: u8 = 32;
+
+/// 状态变量在合约存储中的位置。
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageVariable {
+    pub name: String,
+    pub var_type: TypeName,
+    pub slot: u64,
+    pub offset: u8, // 在槽内从低位算起的字节偏移
+}
+
+/// 合约状态变量的存储布局，遵循 Solidity 的规则：
+///
+/// - 值类型按声明顺序紧凑排列，当前槽剩余的字节放不下时从下一个槽开始；
+/// - 映射、动态数组、`string` 和 `bytes` 独占一个槽，映射元素位于
+///   `keccak256(key . slot)`；
+/// - 定长数组和上述类型总是从新的槽开始，其后的变量也从新的槽开始。
+#[derive(Debug, Clone, Default)]
+pub struct StorageLayout {
+    variables: Vec<StorageVariable>,
+}
+
+/// 类型在存储中占用的空间。
+enum StorageSize {
+    /// 可以与相邻变量共用一个槽的值类型，单位为字节
+    Packed(u8),
+    /// 占用若干个完整的槽
+    Slots(u64),
+}
+
+impl StorageLayout {
+    pub fn new(state_variables: &[StateVariable]) -> Result<Self, SemaError> {
+        let mut variables: Vec<StorageVariable> = Vec::new();
+        let (mut slot, mut offset) = (0u64, 0u8);
+
+        for (index, var) in state_variables.iter().enumerate() {
+            if let Some(first) = state_variables[..index].iter().find(|other| other.name == var.name) {
+                return Err(Diagnostic::error(
+                    codes::DUPLICATE_DECLARATION,
+                    format!("Duplicate state variable: {}", var.name),
+                )
+                .with_label(var.span, "redeclared here")
+                .with_secondary_label(first.span, "first declared here")
+                .into());
+            }
+
+            let unsupported = |message| Diagnostic::error(codes::UNSUPPORTED, message).with_label(var.span, "");
+            let next_slot = |slot, count| next_slot(slot, count).map_err(unsupported);
+            let (var_slot, var_offset) = match storage_size(&var.var_type).map_err(unsupported)? {
+                StorageSize::Packed(size) => {
+                    if offset + size > SLOT_BYTES {
+                        slot = next_slot(slot, 1)?;
+                        offset = 0;
+                    }
+                    let position = (slot, offset);
+                    offset += size;
+                    position
+                }
+                StorageSize::Slots(count) => {
+                    if offset > 0 {
+                        slot = next_slot(slot, 1)?;
+                        offset = 0;
+                    }
+                    let position = (slot, 0);
+                    slot = next_slot(slot, count)?;
+                    position
+                }
+            };
+
+            variables.push(StorageVariable {
+                name: var.name.clone(),
+                var_type: var.var_type.clone(),
+                slot: var_slot,
+                offset: var_offset,
+            });
+        }
+
+        Ok(Self { variables })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StorageVariable> {
+        self.variables.iter().find(|var| var.name == name)
+    }
+
+    pub fn variables(&self) -> &[StorageVariable] {
+        &self.variables
+    }
+}
+
+/// 值类型在存储中占用的字节数。`address` 在 IR 中只用 64 位表示，但在存储中
+/// 仍按 Solidity 的规则占 20 字节。
+pub fn storage_bytes(ty: &IRType) -> Option<u8> {
+    match ty {
+        IRType::Bool => Some(1),
+        IRType::Address => Some(20),
+        IRType::String => None,
+        _ => ty.integer_bits().map(|bits| (bits / 8) as u8),
+    }
+}
+
+fn storage_size(ty: &TypeName) -> Result<StorageSize, String> {
+    match ty {
+        TypeName::FixedBytes(size) => Ok(StorageSize::Packed(*size)),
+        TypeName::String | TypeName::Bytes | TypeName::Mapping(..) | TypeName::Array(_, None) => {
+            Ok(StorageSize::Slots(1))
+        }
+        TypeName::Array(element, Some(length)) => {
+            let slots = match storage_size(element)? {
+                // 元素不跨槽，每个槽放下尽可能多的元素
+                StorageSize::Packed(size) => Some(length.div_ceil((SLOT_BYTES / size) as u64)),
+                StorageSize::Slots(count) => length.checked_mul(count),
+            };
+            slots
+                .map(StorageSize::Slots)
+                .ok_or_else(|| format!("State variable of type {} is too large", ty))
+        }
+        TypeName::UserDefined(_) => Err(format!("Unsupported state variable type: {}", ty)),
+        _ => convert_solidity_type_to_ir_type(ty)
+            .ok()
+            .and_then(|ir_type| storage_bytes(&ir_type))
+            .map(StorageSize::Packed)
+            .ok_or_else(|| format!("Unsupported state variable type: {}", ty)),
+    }
+}
+
+fn next_slot(slot: u64, count: u64) -> Result<u64, String> {
+    slot.checked_add(count)
+        .ok_or_else(|| "Contract storage exceeds the addressable slots".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::lexer::Lexer;
+    use crate::frontend::parser::Parser;
+
+    fn layout(source: &str) -> Result<StorageLayout, String> {
+        let tokens: Vec<_> = Lexer::new(source).collect();
+        let unit = Parser::new(tokens).parse().unwrap();
+        StorageLayout::new(&unit.contracts[0].state_variables).map_err(|e| e.to_string())
+    }
+
+    fn positions(layout: &StorageLayout) -> Vec<(&str, u64, u8)> {
+        layout
+            .variables()
+            .iter()
+            .map(|var| (var.name.as_str(), var.slot, var.offset))
+            .collect()
+    }
+
+    #[test]
+    fn test_value_types_are_packed() {
+        let layout = layout(
+            "contract C {
+                uint128 a; uint128 b; uint c;
+                bool d; address e; uint64 f; uint32 g;
+                mapping(address => uint) m;
+                uint8 h; bytes4 i;
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            positions(&layout),
+            vec![
+                ("a", 0, 0),
+                ("b", 0, 16),
+                ("c", 1, 0),
+                ("d", 2, 0),
+                ("e", 2, 1),
+                ("f", 2, 21),
+                ("g", 3, 0), // 1 + 20 + 8 + 4 > 32
+                ("m", 4, 0),
+                ("h", 5, 0),
+                ("i", 5, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrays_start_new_slots() {
+        let layout = layout(
+            "contract C {
+                uint8 a; uint8[40] b; uint16 c; uint[3][2] d; uint[] e; string f; bool g;
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            positions(&layout),
+            vec![("a", 0, 0), ("b", 1, 0), ("c", 3, 0), ("d", 4, 0), ("e", 10, 0), ("f", 11, 0), ("g", 12, 0)]
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_state_variables() {
+        assert!(layout("contract C { uint a; bool a; }").unwrap_err().contains("Duplicate"));
+        assert!(layout("contract C { Point p; }").unwrap_err().contains("Unsupported state variable type"));
+    }
+}
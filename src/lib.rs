@@ -1,3 +1,5 @@
 pub mod frontend;
 pub mod ir;
-pub mod backend; 
\ No newline at end of file
+pub mod backend;
+pub mod driver;
+pub mod error;
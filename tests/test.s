@@ -3,49 +3,862 @@
 
 .globl add
 add:
-    addi sp, sp, -16
-    sd ra, 8(sp)
-    sd fp, 0(sp)
-    addi fp, sp, 16
-.Lreturn:
-    ld ra, 8(sp)
-    ld fp, 0(sp)
-    addi sp, sp, 16
+    addi sp, sp, -192
+    sd ra, 184(sp)
+    sd fp, 176(sp)
+    addi fp, sp, 192
+    sd a0, -24(fp)
+    ld t5, 0(a1)
+    sd t5, -56(fp)
+    ld t5, 8(a1)
+    sd t5, -48(fp)
+    ld t5, 16(a1)
+    sd t5, -40(fp)
+    ld t5, 24(a1)
+    sd t5, -32(fp)
+    ld t5, 0(a2)
+    sd t5, -88(fp)
+    ld t5, 8(a2)
+    sd t5, -80(fp)
+    ld t5, 16(a2)
+    sd t5, -72(fp)
+    ld t5, 24(a2)
+    sd t5, -64(fp)
+.Ladd_entry:
+    addi a0, fp, -120
+    addi a1, fp, -56
+    addi a2, fp, -88
+    call __u256_add
+    ld t5, -120(fp)
+    ld t6, -24(fp)
+    sd t5, 0(t6)
+    ld t5, -112(fp)
+    ld t6, -24(fp)
+    sd t5, 8(t6)
+    ld t5, -104(fp)
+    ld t6, -24(fp)
+    sd t5, 16(t6)
+    ld t5, -96(fp)
+    ld t6, -24(fp)
+    sd t5, 24(t6)
+    j .Ladd_return
+.Ladd_return:
+    ld ra, 184(sp)
+    ld fp, 176(sp)
+    addi sp, sp, 192
     ret
 
 .globl subtract
 subtract:
-    addi sp, sp, -16
-    sd ra, 8(sp)
-    sd fp, 0(sp)
-    addi fp, sp, 16
-.Lreturn:
-    ld ra, 8(sp)
-    ld fp, 0(sp)
-    addi sp, sp, 16
+    addi sp, sp, -192
+    sd ra, 184(sp)
+    sd fp, 176(sp)
+    addi fp, sp, 192
+    sd a0, -24(fp)
+    ld t5, 0(a1)
+    sd t5, -56(fp)
+    ld t5, 8(a1)
+    sd t5, -48(fp)
+    ld t5, 16(a1)
+    sd t5, -40(fp)
+    ld t5, 24(a1)
+    sd t5, -32(fp)
+    ld t5, 0(a2)
+    sd t5, -88(fp)
+    ld t5, 8(a2)
+    sd t5, -80(fp)
+    ld t5, 16(a2)
+    sd t5, -72(fp)
+    ld t5, 24(a2)
+    sd t5, -64(fp)
+.Lsubtract_entry:
+    addi a0, fp, -120
+    addi a1, fp, -56
+    addi a2, fp, -88
+    call __u256_sub
+    ld t5, -120(fp)
+    ld t6, -24(fp)
+    sd t5, 0(t6)
+    ld t5, -112(fp)
+    ld t6, -24(fp)
+    sd t5, 8(t6)
+    ld t5, -104(fp)
+    ld t6, -24(fp)
+    sd t5, 16(t6)
+    ld t5, -96(fp)
+    ld t6, -24(fp)
+    sd t5, 24(t6)
+    j .Lsubtract_return
+.Lsubtract_return:
+    ld ra, 184(sp)
+    ld fp, 176(sp)
+    addi sp, sp, 192
     ret
 
 .globl multiply
 multiply:
-    addi sp, sp, -16
-    sd ra, 8(sp)
-    sd fp, 0(sp)
-    addi fp, sp, 16
-.Lreturn:
-    ld ra, 8(sp)
-    ld fp, 0(sp)
-    addi sp, sp, 16
+    addi sp, sp, -192
+    sd ra, 184(sp)
+    sd fp, 176(sp)
+    addi fp, sp, 192
+    sd a0, -24(fp)
+    ld t5, 0(a1)
+    sd t5, -56(fp)
+    ld t5, 8(a1)
+    sd t5, -48(fp)
+    ld t5, 16(a1)
+    sd t5, -40(fp)
+    ld t5, 24(a1)
+    sd t5, -32(fp)
+    ld t5, 0(a2)
+    sd t5, -88(fp)
+    ld t5, 8(a2)
+    sd t5, -80(fp)
+    ld t5, 16(a2)
+    sd t5, -72(fp)
+    ld t5, 24(a2)
+    sd t5, -64(fp)
+.Lmultiply_entry:
+    addi a0, fp, -120
+    addi a1, fp, -56
+    addi a2, fp, -88
+    call __u256_mul
+    ld t5, -120(fp)
+    ld t6, -24(fp)
+    sd t5, 0(t6)
+    ld t5, -112(fp)
+    ld t6, -24(fp)
+    sd t5, 8(t6)
+    ld t5, -104(fp)
+    ld t6, -24(fp)
+    sd t5, 16(t6)
+    ld t5, -96(fp)
+    ld t6, -24(fp)
+    sd t5, 24(t6)
+    j .Lmultiply_return
+.Lmultiply_return:
+    ld ra, 184(sp)
+    ld fp, 176(sp)
+    addi sp, sp, 192
     ret
 
 .globl divide
 divide:
-    addi sp, sp, -16
-    sd ra, 8(sp)
-    sd fp, 0(sp)
-    addi fp, sp, 16
-.Lreturn:
-    ld ra, 8(sp)
-    ld fp, 0(sp)
-    addi sp, sp, 16
+    addi sp, sp, -192
+    sd ra, 184(sp)
+    sd fp, 176(sp)
+    addi fp, sp, 192
+    sd a0, -24(fp)
+    ld t5, 0(a1)
+    sd t5, -56(fp)
+    ld t5, 8(a1)
+    sd t5, -48(fp)
+    ld t5, 16(a1)
+    sd t5, -40(fp)
+    ld t5, 24(a1)
+    sd t5, -32(fp)
+    ld t5, 0(a2)
+    sd t5, -88(fp)
+    ld t5, 8(a2)
+    sd t5, -80(fp)
+    ld t5, 16(a2)
+    sd t5, -72(fp)
+    ld t5, 24(a2)
+    sd t5, -64(fp)
+.Ldivide_entry:
+    addi a0, fp, -120
+    addi a1, fp, -56
+    addi a2, fp, -88
+    call __u256_div
+    ld t5, -120(fp)
+    ld t6, -24(fp)
+    sd t5, 0(t6)
+    ld t5, -112(fp)
+    ld t6, -24(fp)
+    sd t5, 8(t6)
+    ld t5, -104(fp)
+    ld t6, -24(fp)
+    sd t5, 16(t6)
+    ld t5, -96(fp)
+    ld t6, -24(fp)
+    sd t5, 24(t6)
+    j .Ldivide_return
+.Ldivide_return:
+    ld ra, 184(sp)
+    ld fp, 176(sp)
+    addi sp, sp, 192
     ret
 
+.globl __u256_add
+__u256_add:
+    li t0, 0
+    ld t1, 0(a1)
+    ld t2, 0(a2)
+    add t1, t1, t0
+    sltu t0, t1, t0
+    add t1, t1, t2
+    sltu t3, t1, t2
+    or t0, t0, t3
+    sd t1, 0(a0)
+    ld t1, 8(a1)
+    ld t2, 8(a2)
+    add t1, t1, t0
+    sltu t0, t1, t0
+    add t1, t1, t2
+    sltu t3, t1, t2
+    or t0, t0, t3
+    sd t1, 8(a0)
+    ld t1, 16(a1)
+    ld t2, 16(a2)
+    add t1, t1, t0
+    sltu t0, t1, t0
+    add t1, t1, t2
+    sltu t3, t1, t2
+    or t0, t0, t3
+    sd t1, 16(a0)
+    ld t1, 24(a1)
+    ld t2, 24(a2)
+    add t1, t1, t0
+    sltu t0, t1, t0
+    add t1, t1, t2
+    sltu t3, t1, t2
+    or t0, t0, t3
+    sd t1, 24(a0)
+    ret
+.globl __u256_sub
+__u256_sub:
+    li t0, 0
+    ld t1, 0(a1)
+    ld t2, 0(a2)
+    sltu t3, t1, t2
+    sub t1, t1, t2
+    sltu t4, t1, t0
+    sub t1, t1, t0
+    or t0, t3, t4
+    sd t1, 0(a0)
+    ld t1, 8(a1)
+    ld t2, 8(a2)
+    sltu t3, t1, t2
+    sub t1, t1, t2
+    sltu t4, t1, t0
+    sub t1, t1, t0
+    or t0, t3, t4
+    sd t1, 8(a0)
+    ld t1, 16(a1)
+    ld t2, 16(a2)
+    sltu t3, t1, t2
+    sub t1, t1, t2
+    sltu t4, t1, t0
+    sub t1, t1, t0
+    or t0, t3, t4
+    sd t1, 16(a0)
+    ld t1, 24(a1)
+    ld t2, 24(a2)
+    sltu t3, t1, t2
+    sub t1, t1, t2
+    sltu t4, t1, t0
+    sub t1, t1, t0
+    or t0, t3, t4
+    sd t1, 24(a0)
+    ret
+.globl __u256_mul
+__u256_mul:
+    li a4, 0
+    li a5, 0
+    li a6, 0
+    li a7, 0
+    ld t0, 0(a1)
+    li t4, 0
+    ld t1, 0(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a4, a4, t2
+    sltu t5, a4, t2
+    add t3, t3, t5
+    add a4, a4, t4
+    sltu t5, a4, t4
+    add t4, t3, t5
+    ld t1, 8(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a5, a5, t2
+    sltu t5, a5, t2
+    add t3, t3, t5
+    add a5, a5, t4
+    sltu t5, a5, t4
+    add t4, t3, t5
+    ld t1, 16(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a6, a6, t2
+    sltu t5, a6, t2
+    add t3, t3, t5
+    add a6, a6, t4
+    sltu t5, a6, t4
+    add t4, t3, t5
+    ld t1, 24(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a7, a7, t2
+    sltu t5, a7, t2
+    add t3, t3, t5
+    add a7, a7, t4
+    sltu t5, a7, t4
+    add t4, t3, t5
+    ld t0, 8(a1)
+    li t4, 0
+    ld t1, 0(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a5, a5, t2
+    sltu t5, a5, t2
+    add t3, t3, t5
+    add a5, a5, t4
+    sltu t5, a5, t4
+    add t4, t3, t5
+    ld t1, 8(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a6, a6, t2
+    sltu t5, a6, t2
+    add t3, t3, t5
+    add a6, a6, t4
+    sltu t5, a6, t4
+    add t4, t3, t5
+    ld t1, 16(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a7, a7, t2
+    sltu t5, a7, t2
+    add t3, t3, t5
+    add a7, a7, t4
+    sltu t5, a7, t4
+    add t4, t3, t5
+    ld t0, 16(a1)
+    li t4, 0
+    ld t1, 0(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a6, a6, t2
+    sltu t5, a6, t2
+    add t3, t3, t5
+    add a6, a6, t4
+    sltu t5, a6, t4
+    add t4, t3, t5
+    ld t1, 8(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a7, a7, t2
+    sltu t5, a7, t2
+    add t3, t3, t5
+    add a7, a7, t4
+    sltu t5, a7, t4
+    add t4, t3, t5
+    ld t0, 24(a1)
+    li t4, 0
+    ld t1, 0(a2)
+    mul t2, t0, t1
+    mulhu t3, t0, t1
+    add a7, a7, t2
+    sltu t5, a7, t2
+    add t3, t3, t5
+    add a7, a7, t4
+    sltu t5, a7, t4
+    add t4, t3, t5
+    sd a4, 0(a0)
+    sd a5, 8(a0)
+    sd a6, 16(a0)
+    sd a7, 24(a0)
+    ret
+.globl __u256_divmod
+__u256_divmod:
+    addi sp, sp, -32
+    sd s1, 0(sp)
+    sd s2, 8(sp)
+    sd s3, 16(sp)
+    sd s4, 24(sp)
+    ld s1, 0(a3)
+    ld a4, 0(a2)
+    li t0, 0
+    ld s2, 8(a3)
+    ld a5, 8(a2)
+    li t1, 0
+    ld s3, 16(a3)
+    ld a6, 16(a2)
+    li t2, 0
+    ld s4, 24(a3)
+    ld a7, 24(a2)
+    li t3, 0
+    or t4, s1, s2
+    or t4, t4, s3
+    or t4, t4, s4
+    bnez t4, .L__u256_divmod_start
+    li a4, 0
+    li a5, 0
+    li a6, 0
+    li a7, 0
+    j .L__u256_divmod_store
+.L__u256_divmod_start:
+    li a2, 256
+.L__u256_divmod_loop:
+    srli t6, t3, 63
+    srli t4, t2, 63
+    slli t3, t3, 1
+    or t3, t3, t4
+    srli t4, t1, 63
+    slli t2, t2, 1
+    or t2, t2, t4
+    srli t4, t0, 63
+    slli t1, t1, 1
+    or t1, t1, t4
+    srli t4, a7, 63
+    slli t0, t0, 1
+    or t0, t0, t4
+    srli t4, a6, 63
+    slli a7, a7, 1
+    or a7, a7, t4
+    srli t4, a5, 63
+    slli a6, a6, 1
+    or a6, a6, t4
+    srli t4, a4, 63
+    slli a5, a5, 1
+    or a5, a5, t4
+    slli a4, a4, 1
+    bnez t6, .L__u256_divmod_subtract
+    bltu t3, s4, .L__u256_divmod_next
+    bltu s4, t3, .L__u256_divmod_subtract
+    bltu t2, s3, .L__u256_divmod_next
+    bltu s3, t2, .L__u256_divmod_subtract
+    bltu t1, s2, .L__u256_divmod_next
+    bltu s2, t1, .L__u256_divmod_subtract
+    bltu t0, s1, .L__u256_divmod_next
+.L__u256_divmod_subtract:
+    sltu t4, t0, s1
+    sub t0, t0, s1
+    sltu t5, t1, s2
+    sub t1, t1, s2
+    sltu t6, t1, t4
+    sub t1, t1, t4
+    or t4, t5, t6
+    sltu t5, t2, s3
+    sub t2, t2, s3
+    sltu t6, t2, t4
+    sub t2, t2, t4
+    or t4, t5, t6
+    sltu t5, t3, s4
+    sub t3, t3, s4
+    sltu t6, t3, t4
+    sub t3, t3, t4
+    or t4, t5, t6
+    ori a4, a4, 1
+.L__u256_divmod_next:
+    addi a2, a2, -1
+    bnez a2, .L__u256_divmod_loop
+.L__u256_divmod_store:
+    beqz a0, .L__u256_divmod_remainder
+    sd a4, 0(a0)
+    sd a5, 8(a0)
+    sd a6, 16(a0)
+    sd a7, 24(a0)
+.L__u256_divmod_remainder:
+    beqz a1, .L__u256_divmod_done
+    sd t0, 0(a1)
+    sd t1, 8(a1)
+    sd t2, 16(a1)
+    sd t3, 24(a1)
+.L__u256_divmod_done:
+    ld s1, 0(sp)
+    ld s2, 8(sp)
+    ld s3, 16(sp)
+    ld s4, 24(sp)
+    addi sp, sp, 32
+    ret
+.globl __u256_div
+__u256_div:
+    mv a3, a2
+    mv a2, a1
+    li a1, 0
+    tail __u256_divmod
+.globl __u256_mod
+__u256_mod:
+    mv a3, a2
+    mv a2, a1
+    mv a1, a0
+    li a0, 0
+    tail __u256_divmod
+.globl __i256_neg
+__i256_neg:
+    li t0, 1
+    ld t1, 0(a1)
+    not t1, t1
+    add t1, t1, t0
+    sltu t0, t1, t0
+    sd t1, 0(a0)
+    ld t1, 8(a1)
+    not t1, t1
+    add t1, t1, t0
+    sltu t0, t1, t0
+    sd t1, 8(a0)
+    ld t1, 16(a1)
+    not t1, t1
+    add t1, t1, t0
+    sltu t0, t1, t0
+    sd t1, 16(a0)
+    ld t1, 24(a1)
+    not t1, t1
+    add t1, t1, t0
+    sltu t0, t1, t0
+    sd t1, 24(a0)
+    ret
+.globl __i256_abs
+__i256_abs:
+    ld t0, 24(a1)
+    bgez t0, .L__i256_abs_copy
+    tail __i256_neg
+.L__i256_abs_copy:
+    ld t0, 0(a1)
+    sd t0, 0(a0)
+    ld t0, 8(a1)
+    sd t0, 8(a0)
+    ld t0, 16(a1)
+    sd t0, 16(a0)
+    ld t0, 24(a1)
+    sd t0, 24(a0)
+    ret
+.globl __i256_div
+__i256_div:
+    addi sp, sp, -96
+    sd ra, 88(sp)
+    sd a0, 80(sp)
+    sd a2, 72(sp)
+    ld t0, 24(a1)
+    ld t1, 24(a2)
+    xor t0, t0, t1
+    srli t0, t0, 63
+    sd t0, 64(sp)
+    mv a0, sp
+    call __i256_abs
+    addi a0, sp, 32
+    ld a1, 72(sp)
+    call __i256_abs
+    ld a0, 80(sp)
+    li a1, 0
+    mv a2, sp
+    addi a3, sp, 32
+    call __u256_divmod
+    ld t0, 64(sp)
+    beqz t0, .L__i256_div_done
+    ld a0, 80(sp)
+    mv a1, a0
+    call __i256_neg
+.L__i256_div_done:
+    ld ra, 88(sp)
+    addi sp, sp, 96
+    ret
+.globl __i256_mod
+__i256_mod:
+    addi sp, sp, -96
+    sd ra, 88(sp)
+    sd a0, 80(sp)
+    sd a2, 72(sp)
+    ld t0, 24(a1)
+    srli t0, t0, 63
+    sd t0, 64(sp)
+    mv a0, sp
+    call __i256_abs
+    addi a0, sp, 32
+    ld a1, 72(sp)
+    call __i256_abs
+    li a0, 0
+    ld a1, 80(sp)
+    mv a2, sp
+    addi a3, sp, 32
+    call __u256_divmod
+    ld t0, 64(sp)
+    beqz t0, .L__i256_mod_done
+    ld a0, 80(sp)
+    mv a1, a0
+    call __i256_neg
+.L__i256_mod_done:
+    ld ra, 88(sp)
+    addi sp, sp, 96
+    ret
+.globl __u256_cmp
+__u256_cmp:
+    ld t0, 24(a0)
+    ld t1, 24(a1)
+    bltu t0, t1, .L__u256_cmp_less
+    bltu t1, t0, .L__u256_cmp_greater
+    ld t0, 16(a0)
+    ld t1, 16(a1)
+    bltu t0, t1, .L__u256_cmp_less
+    bltu t1, t0, .L__u256_cmp_greater
+    ld t0, 8(a0)
+    ld t1, 8(a1)
+    bltu t0, t1, .L__u256_cmp_less
+    bltu t1, t0, .L__u256_cmp_greater
+    ld t0, 0(a0)
+    ld t1, 0(a1)
+    bltu t0, t1, .L__u256_cmp_less
+    bltu t1, t0, .L__u256_cmp_greater
+    li a0, 0
+    ret
+.L__u256_cmp_less:
+    li a0, -1
+    ret
+.L__u256_cmp_greater:
+    li a0, 1
+    ret
+.globl __i256_cmp
+__i256_cmp:
+    ld t0, 24(a0)
+    ld t1, 24(a1)
+    blt t0, t1, .L__i256_cmp_less
+    blt t1, t0, .L__i256_cmp_greater
+    ld t0, 16(a0)
+    ld t1, 16(a1)
+    bltu t0, t1, .L__i256_cmp_less
+    bltu t1, t0, .L__i256_cmp_greater
+    ld t0, 8(a0)
+    ld t1, 8(a1)
+    bltu t0, t1, .L__i256_cmp_less
+    bltu t1, t0, .L__i256_cmp_greater
+    ld t0, 0(a0)
+    ld t1, 0(a1)
+    bltu t0, t1, .L__i256_cmp_less
+    bltu t1, t0, .L__i256_cmp_greater
+    li a0, 0
+    ret
+.L__i256_cmp_less:
+    li a0, -1
+    ret
+.L__i256_cmp_greater:
+    li a0, 1
+    ret
+.globl __u256_and
+__u256_and:
+    ld t0, 0(a1)
+    ld t1, 0(a2)
+    and t0, t0, t1
+    sd t0, 0(a0)
+    ld t0, 8(a1)
+    ld t1, 8(a2)
+    and t0, t0, t1
+    sd t0, 8(a0)
+    ld t0, 16(a1)
+    ld t1, 16(a2)
+    and t0, t0, t1
+    sd t0, 16(a0)
+    ld t0, 24(a1)
+    ld t1, 24(a2)
+    and t0, t0, t1
+    sd t0, 24(a0)
+    ret
+.globl __u256_or
+__u256_or:
+    ld t0, 0(a1)
+    ld t1, 0(a2)
+    or t0, t0, t1
+    sd t0, 0(a0)
+    ld t0, 8(a1)
+    ld t1, 8(a2)
+    or t0, t0, t1
+    sd t0, 8(a0)
+    ld t0, 16(a1)
+    ld t1, 16(a2)
+    or t0, t0, t1
+    sd t0, 16(a0)
+    ld t0, 24(a1)
+    ld t1, 24(a2)
+    or t0, t0, t1
+    sd t0, 24(a0)
+    ret
+.globl __u256_xor
+__u256_xor:
+    ld t0, 0(a1)
+    ld t1, 0(a2)
+    xor t0, t0, t1
+    sd t0, 0(a0)
+    ld t0, 8(a1)
+    ld t1, 8(a2)
+    xor t0, t0, t1
+    sd t0, 8(a0)
+    ld t0, 16(a1)
+    ld t1, 16(a2)
+    xor t0, t0, t1
+    sd t0, 16(a0)
+    ld t0, 24(a1)
+    ld t1, 24(a2)
+    xor t0, t0, t1
+    sd t0, 24(a0)
+    ret
+.globl __u256_shl
+__u256_shl:
+    ld a3, 0(a1)
+    ld a4, 8(a1)
+    ld a5, 16(a1)
+    ld a6, 24(a1)
+    li a7, 0
+    ld t0, 8(a2)
+    ld t1, 16(a2)
+    or t0, t0, t1
+    ld t1, 24(a2)
+    or t0, t0, t1
+    bnez t0, .L__u256_shl_saturate
+    ld t0, 0(a2)
+    li t1, 256
+    bgeu t0, t1, .L__u256_shl_saturate
+    srli t1, t0, 6
+    andi t0, t0, 63
+.L__u256_shl_words:
+    beqz t1, .L__u256_shl_bits
+    mv a6, a5
+    mv a5, a4
+    mv a4, a3
+    mv a3, a7
+    addi t1, t1, -1
+    j .L__u256_shl_words
+.L__u256_shl_bits:
+    beqz t0, .L__u256_shl_store
+    li t2, 64
+    sub t2, t2, t0
+    sll a6, a6, t0
+    srl t3, a5, t2
+    or a6, a6, t3
+    sll a5, a5, t0
+    srl t3, a4, t2
+    or a5, a5, t3
+    sll a4, a4, t0
+    srl t3, a3, t2
+    or a4, a4, t3
+    sll a3, a3, t0
+    j .L__u256_shl_store
+.L__u256_shl_saturate:
+    mv a3, a7
+    mv a4, a7
+    mv a5, a7
+    mv a6, a7
+.L__u256_shl_store:
+    sd a3, 0(a0)
+    sd a4, 8(a0)
+    sd a5, 16(a0)
+    sd a6, 24(a0)
+    ret
+.globl __u256_shr
+__u256_shr:
+    ld a3, 0(a1)
+    ld a4, 8(a1)
+    ld a5, 16(a1)
+    ld a6, 24(a1)
+    li a7, 0
+    ld t0, 8(a2)
+    ld t1, 16(a2)
+    or t0, t0, t1
+    ld t1, 24(a2)
+    or t0, t0, t1
+    bnez t0, .L__u256_shr_saturate
+    ld t0, 0(a2)
+    li t1, 256
+    bgeu t0, t1, .L__u256_shr_saturate
+    srli t1, t0, 6
+    andi t0, t0, 63
+.L__u256_shr_words:
+    beqz t1, .L__u256_shr_bits
+    mv a3, a4
+    mv a4, a5
+    mv a5, a6
+    mv a6, a7
+    addi t1, t1, -1
+    j .L__u256_shr_words
+.L__u256_shr_bits:
+    beqz t0, .L__u256_shr_store
+    li t2, 64
+    sub t2, t2, t0
+    srl a3, a3, t0
+    sll t3, a4, t2
+    or a3, a3, t3
+    srl a4, a4, t0
+    sll t3, a5, t2
+    or a4, a4, t3
+    srl a5, a5, t0
+    sll t3, a6, t2
+    or a5, a5, t3
+    srl a6, a6, t0
+    j .L__u256_shr_store
+.L__u256_shr_saturate:
+    mv a3, a7
+    mv a4, a7
+    mv a5, a7
+    mv a6, a7
+.L__u256_shr_store:
+    sd a3, 0(a0)
+    sd a4, 8(a0)
+    sd a5, 16(a0)
+    sd a6, 24(a0)
+    ret
+.globl __i256_sar
+__i256_sar:
+    ld a3, 0(a1)
+    ld a4, 8(a1)
+    ld a5, 16(a1)
+    ld a6, 24(a1)
+    srai a7, a6, 63
+    ld t0, 8(a2)
+    ld t1, 16(a2)
+    or t0, t0, t1
+    ld t1, 24(a2)
+    or t0, t0, t1
+    bnez t0, .L__i256_sar_saturate
+    ld t0, 0(a2)
+    li t1, 256
+    bgeu t0, t1, .L__i256_sar_saturate
+    srli t1, t0, 6
+    andi t0, t0, 63
+.L__i256_sar_words:
+    beqz t1, .L__i256_sar_bits
+    mv a3, a4
+    mv a4, a5
+    mv a5, a6
+    mv a6, a7
+    addi t1, t1, -1
+    j .L__i256_sar_words
+.L__i256_sar_bits:
+    beqz t0, .L__i256_sar_store
+    li t2, 64
+    sub t2, t2, t0
+    srl a3, a3, t0
+    sll t3, a4, t2
+    or a3, a3, t3
+    srl a4, a4, t0
+    sll t3, a5, t2
+    or a4, a4, t3
+    srl a5, a5, t0
+    sll t3, a6, t2
+    or a5, a5, t3
+    sra a6, a6, t0
+    j .L__i256_sar_store
+.L__i256_sar_saturate:
+    mv a3, a7
+    mv a4, a7
+    mv a5, a7
+    mv a6, a7
+.L__i256_sar_store:
+    sd a3, 0(a0)
+    sd a4, 8(a0)
+    sd a5, 16(a0)
+    sd a6, 24(a0)
+    ret
+.globl __u256_bswap
+__u256_bswap:
+    addi t0, a0, 31
+.L__u256_bswap_loop:
+    bgeu a0, t0, .L__u256_bswap_done
+    lbu t1, 0(a0)
+    lbu t2, 0(t0)
+    sb t2, 0(a0)
+    sb t1, 0(t0)
+    addi a0, a0, 1
+    addi t0, t0, -1
+    j .L__u256_bswap_loop
+.L__u256_bswap_done:
+    ret